@@ -0,0 +1,195 @@
+#![allow(clippy::redundant_field_names)]
+
+use std::fmt;
+
+use serde_json::Value;
+
+int_enum! {
+    /// JSON error codes discord returns with failed requests.
+    pub enum ErrorCode: u32 {
+        General = 0,
+        UnknownAccount = 10001,
+        UnknownApplication = 10002,
+        UnknownChannel = 10003,
+        UnknownGuild = 10004,
+        UnknownIntegration = 10005,
+        UnknownInvite = 10006,
+        UnknownMember = 10007,
+        UnknownMessage = 10008,
+        UnknownOverwrite = 10009,
+        UnknownRole = 10011,
+        UnknownToken = 10012,
+        UnknownUser = 10013,
+        UnknownEmoji = 10014,
+        UnknownWebhook = 10015,
+        UnknownInteraction = 10062,
+        BotsCannotUseEndpoint = 20001,
+        OnlyBotsCanUseEndpoint = 20002,
+        SlowmodeRateLimit = 20016,
+        MaxGuilds = 30001,
+        MaxPins = 30003,
+        MaxRoles = 30005,
+        MaxReactions = 30010,
+        MaxChannels = 30013,
+        Unauthorized = 40001,
+        RequestTooLarge = 40005,
+        AlreadyCrossposted = 40033,
+        MissingAccess = 50001,
+        InvalidAccountType = 50002,
+        CannotExecuteOnDm = 50003,
+        CannotEditMessageByOtherUser = 50005,
+        CannotSendEmptyMessage = 50006,
+        CannotSendMessagesToUser = 50007,
+        CannotSendMessagesInNonTextChannel = 50008,
+        MissingPermissions = 50013,
+        InvalidToken = 50014,
+        NoteTooLong = 50015,
+        InvalidBulkDeleteCount = 50016,
+        InvalidPinChannel = 50019,
+        MessageTooOldToBulkDelete = 50034,
+        InvalidFormBody = 50035,
+        ThreadArchived = 50083,
+        ReactionBlocked = 90001,
+        ThreadLocked = 160005,
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", u32::from(*self))
+    }
+}
+
+/// One invalid field of a request, from the `errors` of an `InvalidFormBody`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    /// Where in the request body the error is, e.g. `embeds[0].description`,
+    /// empty for errors about the whole body.
+    pub path: String,
+    /// e.g. `BASE_TYPE_MAX_LENGTH`.
+    pub code: String,
+    pub message: String,
+}
+
+/// The JSON body of a failed request.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(from = "RawApiError")]
+pub struct ApiError {
+    pub code: ErrorCode,
+    pub message: String,
+    /// The nested error tree flattened to one error per field.
+    pub errors: Vec<FieldError>,
+}
+
+#[derive(Deserialize)]
+struct RawApiError {
+    code: ErrorCode,
+    message: String,
+    #[serde(default)]
+    errors: Value,
+}
+
+#[derive(Deserialize)]
+struct RawFieldError {
+    code: String,
+    message: String,
+}
+
+// the tree nests objects keyed by field name or array index, errors for a
+// field are in its `_errors`
+fn flatten_errors(path: &str, tree: &Value, out: &mut Vec<FieldError>) {
+    let tree = match tree.as_object() {
+        Some(tree) => tree,
+        None => return,
+    };
+    
+    for (key, value) in tree {
+        if key == "_errors" {
+            let errors: Vec<RawFieldError> = serde_json::from_value(value.clone()).unwrap_or_default();
+            out.extend(errors.into_iter().map(|err| FieldError {
+                path: path.to_string(),
+                code: err.code,
+                message: err.message,
+            }));
+            continue
+        }
+        
+        let path = if key.bytes().all(|b| b.is_ascii_digit()) {
+            format!("{}[{}]", path, key)
+        } else if path.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", path, key)
+        };
+        flatten_errors(&path, value, out);
+    }
+}
+
+impl From<RawApiError> for ApiError {
+    fn from(raw: RawApiError) -> Self {
+        let mut errors = Vec::new();
+        flatten_errors("", &raw.errors, &mut errors);
+        
+        ApiError {
+            code: raw.code,
+            message: raw.message,
+            errors: errors,
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (code {})", self.message, self.code)?;
+        for err in &self.errors {
+            match err.path.as_str() {
+                "" => write!(f, ", {}", err.message)?,
+                path => write!(f, ", {}: {}", path, err.message)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn flattens_field_errors() {
+        let err: ApiError = serde_json::from_str(r#"{
+            "code": 50035,
+            "message": "Invalid Form Body",
+            "errors": {
+                "content": {"_errors": [{"code": "BASE_TYPE_MAX_LENGTH", "message": "Must be 2000 or fewer in length."}]},
+                "embeds": {"0": {"fields": {"1": {"name": {"_errors": [{"code": "BASE_TYPE_REQUIRED", "message": "This field is required"}]}}}}}
+            }
+        }"#).unwrap();
+        
+        assert_eq!(err.code, ErrorCode::InvalidFormBody);
+        assert_eq!(err.errors, vec![
+            FieldError {
+                path: "content".into(),
+                code: "BASE_TYPE_MAX_LENGTH".into(),
+                message: "Must be 2000 or fewer in length.".into(),
+            },
+            FieldError {
+                path: "embeds[0].fields[1].name".into(),
+                code: "BASE_TYPE_REQUIRED".into(),
+                message: "This field is required".into(),
+            },
+        ]);
+    }
+    
+    #[test]
+    fn keeps_unknown_codes() {
+        let err: ApiError = serde_json::from_str(r#"{"code": 123456, "message": "New"}"#).unwrap();
+        assert_eq!(err.code, ErrorCode::Unknown(123456));
+        assert!(err.errors.is_empty());
+        
+        let err: ApiError = serde_json::from_str(r#"{"code": 50007, "message": "Cannot send messages to this user"}"#).unwrap();
+        assert_eq!(err.code, ErrorCode::CannotSendMessagesToUser);
+    }
+}
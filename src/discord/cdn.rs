@@ -0,0 +1,154 @@
+
+use std::fmt;
+
+use super::{
+    Snowflake,
+    GuildId,
+    UserId,
+};
+
+pub const CDN_URL: &str = "https://cdn.discordapp.com";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Webp,
+    Gif,
+}
+
+impl ImageFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpg",
+            ImageFormat::Webp => "webp",
+            ImageFormat::Gif => "gif",
+        }
+    }
+}
+
+impl fmt::Display for ImageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.extension())
+    }
+}
+
+/// Format and size of a requested CDN image.
+///
+/// Without a format animated images are requested as gif and everything else
+/// as png.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImageOptions {
+    format: Option<ImageFormat>,
+    size: Option<u16>,
+}
+
+impl ImageOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    pub fn format(mut self, format: ImageFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+    
+    /// Discord only serves powers of two between 16 and 4096, other sizes are
+    /// rounded up to the next one it accepts.
+    pub fn size(mut self, size: u16) -> Self {
+        self.size = Some(size.clamp(16, 4096).next_power_of_two());
+        self
+    }
+    
+    fn url(&self, path: fmt::Arguments, animated: bool) -> String {
+        let format = match self.format {
+            Some(format) => format,
+            None if animated => ImageFormat::Gif,
+            None => ImageFormat::Png,
+        };
+        
+        let mut url = format!("{}/{}.{}", CDN_URL, path, format);
+        if let Some(size) = self.size {
+            url.push_str(&format!("?size={}", size));
+        }
+        url
+    }
+}
+
+fn is_animated(hash: &str) -> bool {
+    hash.starts_with("a_")
+}
+
+pub fn user_avatar(user: UserId, hash: &str, options: ImageOptions) -> String {
+    options.url(format_args!("avatars/{}/{}", user, hash), is_animated(hash))
+}
+
+/// The avatar shown for users without one. Users that migrated to the new
+/// username system have a discriminator of `"0"` and are assigned one based
+/// on their id instead.
+pub fn default_avatar(user: UserId, discriminator: &str) -> String {
+    let index = match discriminator.parse::<u64>() {
+        Ok(0) | Err(_) => (*user.0 >> 22) % 6,
+        Ok(discriminator) => discriminator % 5,
+    };
+    format!("{}/embed/avatars/{}.png", CDN_URL, index)
+}
+
+pub fn user_banner(user: UserId, hash: &str, options: ImageOptions) -> String {
+    options.url(format_args!("banners/{}/{}", user, hash), is_animated(hash))
+}
+
+pub fn guild_icon(guild: GuildId, hash: &str, options: ImageOptions) -> String {
+    options.url(format_args!("icons/{}/{}", guild, hash), is_animated(hash))
+}
+
+pub fn emoji(emoji: Snowflake, animated: bool, options: ImageOptions) -> String {
+    options.url(format_args!("emojis/{}", emoji), animated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const USER: UserId = UserId(Snowflake(80351110224678912));
+    
+    #[test]
+    fn sizes_are_powers_of_two_in_range() {
+        let size = |size| ImageOptions::new().size(size).size.unwrap();
+        
+        assert_eq!(size(0), 16);
+        assert_eq!(size(16), 16);
+        assert_eq!(size(100), 128);
+        assert_eq!(size(128), 128);
+        assert_eq!(size(129), 256);
+        assert_eq!(size(4096), 4096);
+        assert_eq!(size(u16::MAX), 4096);
+    }
+    
+    #[test]
+    fn animated_hashes_default_to_gif() {
+        assert_eq!(user_avatar(USER, "a_1269e74af4df7417b13759eae50c83dc", ImageOptions::new()),
+            "https://cdn.discordapp.com/avatars/80351110224678912/a_1269e74af4df7417b13759eae50c83dc.gif");
+        assert_eq!(user_avatar(USER, "8342729096ea3675442027381ff50dfe", ImageOptions::new().size(64)),
+            "https://cdn.discordapp.com/avatars/80351110224678912/8342729096ea3675442027381ff50dfe.png?size=64");
+        
+        // an explicit format wins
+        assert_eq!(user_avatar(USER, "a_1269e74af4df7417b13759eae50c83dc", ImageOptions::new().format(ImageFormat::Webp)),
+            "https://cdn.discordapp.com/avatars/80351110224678912/a_1269e74af4df7417b13759eae50c83dc.webp");
+        assert_eq!(emoji(Snowflake(1105891587839529041), true, ImageOptions::new()),
+            "https://cdn.discordapp.com/emojis/1105891587839529041.gif");
+    }
+    
+    #[test]
+    fn default_avatars() {
+        // migrated users: (id >> 22) % 6
+        assert_eq!(default_avatar(USER, "0"), "https://cdn.discordapp.com/embed/avatars/5.png");
+        assert_eq!(default_avatar(UserId(Snowflake(5 << 22)), "0"), "https://cdn.discordapp.com/embed/avatars/5.png");
+        assert_eq!(default_avatar(UserId(Snowflake(6 << 22)), "0"), "https://cdn.discordapp.com/embed/avatars/0.png");
+        
+        // legacy discriminators: discriminator % 5
+        assert_eq!(default_avatar(USER, "4163"), "https://cdn.discordapp.com/embed/avatars/3.png");
+        assert_eq!(default_avatar(USER, "0005"), "https://cdn.discordapp.com/embed/avatars/0.png");
+    }
+}
@@ -0,0 +1,227 @@
+
+use super::{
+    Extra,
+    double_option,
+    Snowflake,
+    GuildId,
+    ChannelId,
+    UserId,
+    MessageId,
+    Permissions,
+    Timestamp,
+    User,
+};
+
+int_enum! {
+    pub enum ChannelType {
+        GuildText = 0,
+        Dm = 1,
+        GuildVoice = 2,
+        GroupDm = 3,
+        GuildCatagory = 4,
+        GuildNews = 5,
+        GuildStore = 6,
+        AnnouncementThread = 10,
+        PublicThread = 11,
+        PrivateThread = 12,
+        GuildStageVoice = 13,
+        GuildDirectory = 14,
+        GuildForum = 15,
+        GuildMedia = 16,
+    }
+}
+
+int_enum! {
+    pub enum OverwriteType {
+        Role = 0,
+        Member = 1,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Overwrite {
+    pub id: Snowflake,
+    #[serde(rename = "type")]
+    pub overwrite_type: OverwriteType,
+    pub allow: Permissions,
+    pub deny: Permissions,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadMetadata {
+    pub archived: bool,
+    pub auto_archive_duration: u64,
+    pub archive_timestamp: Timestamp,
+    pub locked: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invitable: Option<bool>,
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub create_timestamp: Option<Option<Timestamp>>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// A channel as sent by the gateway and returned by `SendHandle::get_channel`.
+///
+/// Which fields are present depends on the channel type, guild channels sent
+/// in GUILD_CREATE also lack `guild_id`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Channel {
+    pub id: ChannelId,
+    #[serde(rename = "type")]
+    channel_type: ChannelType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guild_id: Option<GuildId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    position: Option<isize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permission_overwrites: Option<Vec<Overwrite>>,
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    name: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    topic: Option<Option<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nsfw: Option<bool>,
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    last_message_id: Option<Option<MessageId>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bitrate: Option<isize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user_limit: Option<isize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rate_limit_per_user: Option<isize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    recipients: Option<Vec<User>>,
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    icon: Option<Option<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    owner_id: Option<UserId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    application_id: Option<Snowflake>,
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    parent_id: Option<Option<ChannelId>>,
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    last_pin_timestamp: Option<Option<Timestamp>>,
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    rtc_region: Option<Option<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    video_quality_mode: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message_count: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    member_count: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thread_metadata: Option<ThreadMetadata>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    default_auto_archive_duration: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    flags: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    applied_tags: Option<Vec<Snowflake>>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+impl Channel {
+    pub fn channel_type(&self) -> &ChannelType {
+        &self.channel_type
+    }
+    
+    pub fn position(&self) -> Option<isize> {
+        self.position
+    }
+    
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_ref().and_then(|x| x.as_deref())
+    }
+    
+    pub fn topic(&self) -> Option<&str> {
+        self.topic.as_ref().and_then(|x| x.as_deref())
+    }
+    
+    pub fn nsfw(&self) -> bool {
+        self.nsfw.unwrap_or(false)
+    }
+    
+    pub fn last_message_id(&self) -> Option<MessageId> {
+        self.last_message_id.flatten()
+    }
+    
+    pub fn bitrate(&self) -> Option<isize> {
+        self.bitrate
+    }
+    
+    pub fn user_limit(&self) -> Option<isize> {
+        self.user_limit
+    }
+    
+    /// Slowmode in seconds.
+    pub fn rate_limit_per_user(&self) -> Option<isize> {
+        self.rate_limit_per_user
+    }
+    
+    /// Recipients of a DM or group DM.
+    pub fn recipients(&self) -> &[User] {
+        self.recipients.as_deref().unwrap_or(&[])
+    }
+    
+    pub fn icon(&self) -> Option<&str> {
+        self.icon.as_ref().and_then(|x| x.as_deref())
+    }
+    
+    pub fn owner_id(&self) -> Option<UserId> {
+        self.owner_id
+    }
+    
+    pub fn application_id(&self) -> Option<Snowflake> {
+        self.application_id
+    }
+    
+    /// The category of a guild channel or the channel a thread was created in.
+    pub fn parent_id(&self) -> Option<ChannelId> {
+        self.parent_id.flatten()
+    }
+    
+    pub fn last_pin_timestamp(&self) -> Option<Timestamp> {
+        self.last_pin_timestamp.flatten()
+    }
+    
+    pub fn rtc_region(&self) -> Option<&str> {
+        self.rtc_region.as_ref().and_then(|x| x.as_deref())
+    }
+    
+    pub fn video_quality_mode(&self) -> Option<u64> {
+        self.video_quality_mode
+    }
+    
+    pub fn message_count(&self) -> Option<u64> {
+        self.message_count
+    }
+    
+    pub fn member_count(&self) -> Option<u64> {
+        self.member_count
+    }
+    
+    pub fn thread_metadata(&self) -> Option<&ThreadMetadata> {
+        self.thread_metadata.as_ref()
+    }
+    
+    pub fn default_auto_archive_duration(&self) -> Option<u64> {
+        self.default_auto_archive_duration
+    }
+    
+    pub fn flags(&self) -> u64 {
+        self.flags.unwrap_or(0)
+    }
+    
+    pub fn applied_tags(&self) -> &[Snowflake] {
+        self.applied_tags.as_deref().unwrap_or(&[])
+    }
+    
+    pub fn is_thread(&self) -> bool {
+        matches!(self.channel_type,
+            ChannelType::AnnouncementThread | ChannelType::PublicThread | ChannelType::PrivateThread)
+    }
+}
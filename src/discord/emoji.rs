@@ -0,0 +1,254 @@
+#![allow(clippy::redundant_field_names)]
+
+use std::fmt;
+use std::str::FromStr;
+
+use percent_encoding::{percent_encode, NON_ALPHANUMERIC};
+
+use super::{
+    Extra,
+    Snowflake,
+    RoleId,
+    User,
+    PartialEmoji,
+    ImageOptions,
+    cdn,
+};
+
+/// A guild's custom emoji, `id` is `None` for unicode emoji.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Emoji {
+    pub id: Option<Snowflake>,
+    /// `None` for deleted emoji in reactions.
+    pub name: Option<String>,
+    #[serde(default)]
+    pub roles: Vec<RoleId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<User>,
+    #[serde(default)]
+    pub require_colons: bool,
+    #[serde(default)]
+    pub managed: bool,
+    #[serde(default)]
+    pub animated: bool,
+    #[serde(default = "default_true")]
+    pub available: bool,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+impl Emoji {
+    /// `None` for unicode emoji.
+    pub fn url(&self, options: ImageOptions) -> Option<String> {
+        self.id.map(|id| cdn::emoji(id, self.animated, options))
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// The emoji of a reaction, either a unicode emoji or a guild's custom emoji.
+///
+/// Parses from the emoji itself, mention syntax (`<:name:id>`, `<a:name:id>`)
+/// or `name:id`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "PartialEmoji", into = "PartialEmoji")]
+pub enum ReactionType {
+    Unicode(String),
+    Custom {
+        id: Snowflake,
+        /// `None` for emoji that have been deleted.
+        name: Option<String>,
+        animated: bool,
+    },
+}
+
+impl ReactionType {
+    /// The emoji as used in reaction endpoint paths.
+    pub fn url_segment(&self) -> String {
+        match self {
+            ReactionType::Unicode(emoji) => {
+                percent_encode(emoji.as_bytes(), NON_ALPHANUMERIC).to_string()
+            }
+            ReactionType::Custom { id, name, .. } => {
+                // discord only looks at the id but wants some name
+                let name = name.as_deref().unwrap_or("_");
+                format!("{}:{}", percent_encode(name.as_bytes(), NON_ALPHANUMERIC), id)
+            }
+        }
+    }
+    
+    /// `None` for unicode emoji.
+    pub fn url(&self, options: ImageOptions) -> Option<String> {
+        match self {
+            ReactionType::Unicode(_) => None,
+            ReactionType::Custom { id, animated, .. } => Some(cdn::emoji(*id, *animated, options)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReactionTypeParseError {
+    input: String,
+}
+
+impl fmt::Display for ReactionTypeParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid reaction emoji: {:?}", self.input)
+    }
+}
+
+impl std::error::Error for ReactionTypeParseError {}
+
+impl FromStr for ReactionType {
+    type Err = ReactionTypeParseError;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ReactionTypeParseError { input: s.into() };
+        let s = s.trim();
+        
+        let (animated, custom) = match s.strip_prefix('<').and_then(|x| x.strip_suffix('>')) {
+            Some(mention) => match mention.strip_prefix("a:") {
+                Some(rest) => (true, rest),
+                None => (false, mention.strip_prefix(':').ok_or_else(err)?),
+            },
+            None if s.contains(':') => (false, s),
+            None if s.is_empty() => return Err(err()),
+            None => return Ok(ReactionType::Unicode(s.into())),
+        };
+        
+        let (name, id) = custom.split_once(':').ok_or_else(err)?;
+        if name.contains(['<', '>']) {
+            return Err(err())
+        }
+        let id = id.parse().map_err(|_| err())?;
+        
+        Ok(ReactionType::Custom {
+            id: id,
+            name: if name.is_empty() { None } else { Some(name.into()) },
+            animated: animated,
+        })
+    }
+}
+
+impl fmt::Display for ReactionType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReactionType::Unicode(emoji) => f.write_str(emoji),
+            ReactionType::Custom { id, name, animated } => {
+                let prefix = if *animated { "a" } else { "" };
+                write!(f, "<{}:{}:{}>", prefix, name.as_deref().unwrap_or("_"), id)
+            }
+        }
+    }
+}
+
+impl From<char> for ReactionType {
+    fn from(emoji: char) -> Self {
+        ReactionType::Unicode(emoji.to_string())
+    }
+}
+
+impl From<PartialEmoji> for ReactionType {
+    fn from(emoji: PartialEmoji) -> Self {
+        match emoji.id {
+            Some(id) => ReactionType::Custom {
+                id: id,
+                name: emoji.name,
+                animated: emoji.animated.unwrap_or(false),
+            },
+            None => ReactionType::Unicode(emoji.name.unwrap_or_default()),
+        }
+    }
+}
+
+impl From<ReactionType> for PartialEmoji {
+    fn from(reaction: ReactionType) -> Self {
+        match reaction {
+            ReactionType::Unicode(emoji) => PartialEmoji {
+                id: None,
+                name: Some(emoji),
+                animated: None,
+                extra: Extra::new(),
+            },
+            ReactionType::Custom { id, name, animated } => PartialEmoji {
+                id: Some(id),
+                name: name,
+                animated: Some(animated),
+                extra: Extra::new(),
+            },
+        }
+    }
+}
+
+impl From<Emoji> for ReactionType {
+    fn from(emoji: Emoji) -> Self {
+        match emoji.id {
+            Some(id) => ReactionType::Custom {
+                id: id,
+                name: emoji.name,
+                animated: emoji.animated,
+            },
+            None => ReactionType::Unicode(emoji.name.unwrap_or_default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn parse(s: &str) -> Result<ReactionType, ReactionTypeParseError> {
+        s.parse()
+    }
+    
+    fn custom(id: u64, name: &str, animated: bool) -> ReactionType {
+        ReactionType::Custom {
+            id: Snowflake(id),
+            name: Some(name.into()),
+            animated: animated,
+        }
+    }
+    
+    #[test]
+    fn parses_custom_emoji() {
+        assert_eq!(parse("<a:catjam:1105891587839529041>"), Ok(custom(1105891587839529041, "catjam", true)));
+        assert_eq!(parse("<:catjam:1105891587839529041>"), Ok(custom(1105891587839529041, "catjam", false)));
+        assert_eq!(parse("catjam:1105891587839529041"), Ok(custom(1105891587839529041, "catjam", false)));
+        assert_eq!(parse(" <:catjam:1105891587839529041> "), Ok(custom(1105891587839529041, "catjam", false)));
+        
+        assert_eq!(custom(1105891587839529041, "catjam", true).to_string(), "<a:catjam:1105891587839529041>");
+        assert_eq!(custom(1105891587839529041, "catjam", false).url_segment(), "catjam:1105891587839529041");
+    }
+    
+    #[test]
+    fn parses_unicode_emoji() {
+        // family: man, woman, girl joined by zero width joiners
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        let emoji = parse(family).unwrap();
+        
+        assert_eq!(emoji, ReactionType::Unicode(family.into()));
+        assert_eq!(emoji.to_string(), family);
+        assert_eq!(emoji.url_segment(), "%F0%9F%91%A8%E2%80%8D%F0%9F%91%A9%E2%80%8D%F0%9F%91%A7");
+        assert_eq!(ReactionType::from('\u{1F44D}').url_segment(), "%F0%9F%91%8D");
+    }
+    
+    #[test]
+    fn rejects_malformed() {
+        for s in [
+            "",
+            "   ",
+            "<:name>",
+            "<a:name:notanid>",
+            "<:name:>",
+            "<name:123>",
+            "<:name:123",
+            "name:123>",
+            "name:-1",
+            "a:b:123",
+        ] {
+            assert!(parse(s).is_err(), "{:?} parsed", s);
+        }
+    }
+}
@@ -0,0 +1,53 @@
+// Models for unit tests, parsed from the captured payloads in
+// `tests/payloads` with the fields a test cares about replaced.
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use super::{
+    Channel,
+    Guild,
+    GuildMember,
+    Message,
+    Role,
+};
+
+const MESSAGE: &str = include_str!("../../tests/payloads/message_create.json");
+const GUILD: &str = include_str!("../../tests/payloads/guild_create.json");
+const MEMBER: &str = include_str!("../../tests/payloads/guild_member.json");
+const ROLE: &str = include_str!("../../tests/payloads/role.json");
+
+fn with_fields<T: DeserializeOwned>(mut payload: Value, fields: Value) -> T {
+    let fields = match fields {
+        Value::Object(fields) => fields,
+        other => panic!("fields must be an object, got {}", other),
+    };
+    payload.as_object_mut().unwrap().extend(fields);
+    serde_json::from_value(payload).unwrap()
+}
+
+fn load<T: DeserializeOwned>(payload: &str, fields: Value) -> T {
+    with_fields(serde_json::from_str(payload).unwrap(), fields)
+}
+
+pub(crate) fn message(fields: Value) -> Message {
+    load(MESSAGE, fields)
+}
+
+pub(crate) fn guild(fields: Value) -> Guild {
+    load(GUILD, fields)
+}
+
+pub(crate) fn member(fields: Value) -> GuildMember {
+    load(MEMBER, fields)
+}
+
+pub(crate) fn role(fields: Value) -> Role {
+    load(ROLE, fields)
+}
+
+/// The first channel of the guild payload.
+pub(crate) fn channel(fields: Value) -> Channel {
+    let guild: Value = serde_json::from_str(GUILD).unwrap();
+    with_fields(guild["channels"][0].clone(), fields)
+}
@@ -0,0 +1,159 @@
+
+use super::{
+    Extra,
+    Snowflake,
+    GuildId,
+    ChannelId,
+    UserId,
+    Timestamp,
+    User,
+    Role,
+    GuildMember,
+    Channel,
+    Emoji,
+    Presence,
+    StickerFormatType,
+    ImageOptions,
+    cdn,
+};
+
+/// A guild as sent in GUILD_CREATE and GUILD_UPDATE.
+///
+/// `joined_at` through `threads` are only sent with GUILD_CREATE and are
+/// empty or `None` otherwise.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Guild {
+    pub id: GuildId,
+    pub name: String,
+    pub icon: Option<String>,
+    pub splash: Option<String>,
+    pub discovery_splash: Option<String>,
+    pub banner: Option<String>,
+    pub description: Option<String>,
+    pub owner_id: UserId,
+    pub afk_channel_id: Option<ChannelId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub afk_timeout: Option<u64>,
+    pub system_channel_id: Option<ChannelId>,
+    pub rules_channel_id: Option<ChannelId>,
+    pub public_updates_channel_id: Option<ChannelId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verification_level: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_message_notifications: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explicit_content_filter: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mfa_level: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nsfw_level: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub premium_tier: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub premium_subscription_count: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preferred_locale: Option<String>,
+    pub vanity_url_code: Option<String>,
+    pub application_id: Option<Snowflake>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_members: Option<u64>,
+    pub roles: Vec<Role>,
+    #[serde(default)]
+    pub emojis: Vec<Emoji>,
+    #[serde(default)]
+    pub stickers: Vec<Sticker>,
+    #[serde(default)]
+    pub features: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub joined_at: Option<Timestamp>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub large: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unavailable: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub member_count: Option<u64>,
+    #[serde(default)]
+    pub members: Vec<GuildMember>,
+    #[serde(default)]
+    pub voice_states: Vec<VoiceState>,
+    #[serde(default)]
+    pub presences: Vec<Presence>,
+    #[serde(default)]
+    pub channels: Vec<Channel>,
+    #[serde(default)]
+    pub threads: Vec<Channel>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+impl Guild {
+    pub fn icon_url(&self, options: ImageOptions) -> Option<String> {
+        self.icon.as_ref().map(|hash| cdn::guild_icon(self.id, hash, options))
+    }
+    
+    pub fn role(&self, id: super::RoleId) -> Option<&Role> {
+        self.roles.iter().find(|role| role.id == id)
+    }
+    
+    pub fn member(&self, id: UserId) -> Option<&GuildMember> {
+        self.members.iter().find(|member| member.user.as_ref().is_some_and(|user| user.id == id))
+    }
+    
+    pub fn channel(&self, id: ChannelId) -> Option<&Channel> {
+        self.channels.iter().chain(self.threads.iter()).find(|channel| channel.id == id)
+    }
+}
+
+/// A user's voice connection state, also sent as VOICE_STATE_UPDATE.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoiceState {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guild_id: Option<GuildId>,
+    /// `None` when the user left voice.
+    pub channel_id: Option<ChannelId>,
+    pub user_id: UserId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub member: Option<GuildMember>,
+    pub session_id: String,
+    pub deaf: bool,
+    pub mute: bool,
+    pub self_deaf: bool,
+    pub self_mute: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub self_stream: Option<bool>,
+    pub self_video: bool,
+    pub suppress: bool,
+    pub request_to_speak_timestamp: Option<Timestamp>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+int_enum! {
+    pub enum StickerType {
+        Standard = 1,
+        Guild = 2,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sticker {
+    pub id: Snowflake,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pack_id: Option<Snowflake>,
+    pub name: String,
+    pub description: Option<String>,
+    pub tags: String,
+    #[serde(rename = "type")]
+    pub sticker_type: StickerType,
+    pub format_type: StickerFormatType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub available: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guild_id: Option<GuildId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<User>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_value: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
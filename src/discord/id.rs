@@ -0,0 +1,61 @@
+
+use std::fmt;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+use super::Snowflake;
+
+macro_rules! id_type {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+        #[serde(transparent)]
+        pub struct $name(pub Snowflake);
+        
+        impl std::ops::Deref for $name {
+            type Target = Snowflake;
+            
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+        
+        impl From<Snowflake> for $name {
+            fn from(id: Snowflake) -> Self {
+                $name(id)
+            }
+        }
+        
+        impl From<$name> for Snowflake {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+        
+        impl From<u64> for $name {
+            fn from(id: u64) -> Self {
+                $name(Snowflake(id))
+            }
+        }
+        
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+        
+        impl FromStr for $name {
+            type Err = ParseIntError;
+            
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                s.parse().map($name)
+            }
+        }
+    };
+}
+
+id_type!(GuildId);
+id_type!(ChannelId);
+id_type!(UserId);
+id_type!(RoleId);
+id_type!(MessageId);
@@ -0,0 +1,651 @@
+
+use super::{
+    Extra,
+    double_option,
+    is_false,
+    Timestamp,
+    Snowflake,
+    GuildId,
+    ChannelId,
+    RoleId,
+    MessageId,
+    User,
+    GuildMember,
+    Channel,
+    ReactionType,
+    ImageOptions,
+    cdn,
+};
+
+/// Set by the sender to check a message was sent, discord hands it back as
+/// it was given.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Nonce {
+    String(String),
+    Int(i64),
+    LargerInt(u64),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Message {
+    pub id: MessageId,
+    pub channel_id: ChannelId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guild_id: Option<GuildId>,
+    pub author: User,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub member: Option<GuildMember>,
+    pub content: String,
+    pub timestamp: Timestamp,
+    pub edited_timestamp: Option<Timestamp>,
+    pub tts: bool,
+    pub mention_everyone: bool,
+    pub mentions: Vec<User>, // User + extra data?
+    // pub mention_roles: Vec<Role>,
+    pub mention_roles: Vec<RoleId>,
+    pub attachments: Vec<Attachment>,
+    pub embeds: Vec<Embed>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reactions: Vec<Reaction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<Nonce>,
+    pub pinned: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub webhook_id: Option<Snowflake>,
+    #[serde(rename="type")]
+    pub message_type: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub activity: Option<MessageActivity>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub application: Option<MessageApplication>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub application_id: Option<Snowflake>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_reference: Option<MessageReference>,
+    #[serde(default)]
+    pub flags: u64,
+    /// `Some(None)` when the message this replies to has been deleted.
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub referenced_message: Option<Option<Box<Message>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interaction: Option<MessageInteraction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thread: Option<Channel>,
+    #[serde(default)]
+    pub components: Vec<Component>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sticker_items: Vec<StickerItem>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attachment {
+    pub id: Snowflake,
+    pub filename: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    pub size: u64,
+    pub url: String,
+    pub proxy_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ephemeral: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub waveform: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flags: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// An embed as received from discord, see `send_message::Embed` for sending.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Embed {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub embed_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<Timestamp>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub footer: Option<EmbedFooter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<EmbedImage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<EmbedImage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub video: Option<EmbedVideo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<EmbedProvider>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<EmbedAuthor>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<EmbedField>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbedFooter {
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy_icon_url: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbedImage {
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbedVideo {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbedProvider {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbedAuthor {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy_icon_url: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbedField {
+    pub name: String,
+    pub value: String,
+    #[serde(default)]
+    pub inline: bool,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reaction {
+    pub count: u64,
+    pub me: bool,
+    pub emoji: PartialEmoji,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+impl Reaction {
+    /// The emoji in the form the reaction endpoints take.
+    pub fn reaction_type(&self) -> ReactionType {
+        self.emoji.clone().into()
+    }
+}
+
+/// Emoji as it appears in reactions and components, `id` is `None` for
+/// unicode emoji and `name` can be `None` for deleted custom emoji.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartialEmoji {
+    pub id: Option<Snowflake>,
+    pub name: Option<String>,
+    /// Usually only sent for custom emoji.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub animated: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+impl PartialEmoji {
+    pub fn is_animated(&self) -> bool {
+        self.animated.unwrap_or(false)
+    }
+    
+    /// `None` for unicode emoji.
+    pub fn url(&self, options: ImageOptions) -> Option<String> {
+        self.id.map(|id| cdn::emoji(id, self.is_animated(), options))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageActivity {
+    #[serde(rename = "type")]
+    pub activity_type: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub party_id: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageApplication {
+    pub id: Snowflake,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub icon: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cover_image: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageReference {
+    #[serde(rename = "type", default)]
+    pub reference_type: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<MessageId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel_id: Option<ChannelId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guild_id: Option<GuildId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fail_if_not_exists: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageInteraction {
+    pub id: Snowflake,
+    #[serde(rename = "type")]
+    pub interaction_type: u64,
+    pub name: String,
+    pub user: User,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+int_enum! {
+    pub enum StickerFormatType {
+        Png = 1,
+        Apng = 2,
+        Lottie = 3,
+        Gif = 4,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StickerItem {
+    pub id: Snowflake,
+    pub name: String,
+    pub format_type: StickerFormatType,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+int_enum! {
+    pub enum ComponentType {
+        ActionRow = 1,
+        Button = 2,
+        StringSelect = 3,
+        TextInput = 4,
+        UserSelect = 5,
+        RoleSelect = 6,
+        MentionableSelect = 7,
+        ChannelSelect = 8,
+    }
+}
+
+/// A message component, action rows hold their children in `components`
+/// and the remaining fields are set depending on `component_type`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Component {
+    #[serde(rename = "type")]
+    pub component_type: ComponentType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub style: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub emoji: Option<PartialEmoji>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sku_id: Option<Snowflake>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<Vec<SelectOption>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub placeholder: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_values: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_values: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub components: Vec<Component>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelectOption {
+    pub label: String,
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub emoji: Option<PartialEmoji>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub default: bool,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// The partial message sent with MESSAGE_UPDATE, only `id` and `channel_id` are
+/// guaranteed. Fields are `None` when absent, nullable fields are `Some(None)`
+/// when explicitly null.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageUpdate {
+    pub id: MessageId,
+    pub channel_id: ChannelId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guild_id: Option<GuildId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<User>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub member: Option<GuildMember>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<Timestamp>,
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub edited_timestamp: Option<Option<Timestamp>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tts: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mention_everyone: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mentions: Option<Vec<User>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mention_roles: Option<Vec<RoleId>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attachments: Option<Vec<Attachment>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embeds: Option<Vec<Embed>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reactions: Option<Vec<Reaction>>,
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<Option<Nonce>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pinned: Option<bool>,
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub webhook_id: Option<Option<Snowflake>>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub message_type: Option<u64>,
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub activity: Option<Option<MessageActivity>>,
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub application: Option<Option<MessageApplication>>,
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub application_id: Option<Option<Snowflake>>,
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub message_reference: Option<Option<MessageReference>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flags: Option<u64>,
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub referenced_message: Option<Option<Box<Message>>>,
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub interaction: Option<Option<MessageInteraction>>,
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub thread: Option<Option<Channel>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub components: Option<Vec<Component>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sticker_items: Option<Vec<StickerItem>>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+impl Message {
+    /// Applies the fields present in a MESSAGE_UPDATE to this message,
+    /// leaving everything the update omitted untouched. Fields that aren't
+    /// modeled are replaced one by one in `extra`.
+    pub fn apply_update(&mut self, update: MessageUpdate) {
+        if let Some(guild_id) = update.guild_id {
+            self.guild_id = Some(guild_id);
+        }
+        if let Some(author) = update.author {
+            self.author = author;
+        }
+        if let Some(member) = update.member {
+            self.member = Some(member);
+        }
+        if let Some(content) = update.content {
+            self.content = content;
+        }
+        if let Some(timestamp) = update.timestamp {
+            self.timestamp = timestamp;
+        }
+        if let Some(edited_timestamp) = update.edited_timestamp {
+            self.edited_timestamp = edited_timestamp;
+        }
+        if let Some(tts) = update.tts {
+            self.tts = tts;
+        }
+        if let Some(mention_everyone) = update.mention_everyone {
+            self.mention_everyone = mention_everyone;
+        }
+        if let Some(mentions) = update.mentions {
+            self.mentions = mentions;
+        }
+        if let Some(mention_roles) = update.mention_roles {
+            self.mention_roles = mention_roles;
+        }
+        if let Some(attachments) = update.attachments {
+            self.attachments = attachments;
+        }
+        if let Some(embeds) = update.embeds {
+            self.embeds = embeds;
+        }
+        if let Some(reactions) = update.reactions {
+            self.reactions = reactions;
+        }
+        if let Some(nonce) = update.nonce {
+            self.nonce = nonce;
+        }
+        if let Some(pinned) = update.pinned {
+            self.pinned = pinned;
+        }
+        if let Some(webhook_id) = update.webhook_id {
+            self.webhook_id = webhook_id;
+        }
+        if let Some(message_type) = update.message_type {
+            self.message_type = message_type;
+        }
+        if let Some(activity) = update.activity {
+            self.activity = activity;
+        }
+        if let Some(application) = update.application {
+            self.application = application;
+        }
+        if let Some(application_id) = update.application_id {
+            self.application_id = application_id;
+        }
+        if let Some(message_reference) = update.message_reference {
+            self.message_reference = message_reference;
+        }
+        if let Some(flags) = update.flags {
+            self.flags = flags;
+        }
+        if let Some(referenced_message) = update.referenced_message {
+            self.referenced_message = Some(referenced_message);
+        }
+        if let Some(interaction) = update.interaction {
+            self.interaction = interaction;
+        }
+        if let Some(thread) = update.thread {
+            self.thread = thread;
+        }
+        if let Some(components) = update.components {
+            self.components = components;
+        }
+        if let Some(sticker_items) = update.sticker_items {
+            self.sticker_items = sticker_items;
+        }
+        self.extra.extend(update.extra);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    use crate::discord::fixtures;
+    
+    // the captured message with some of its fields changed, a reply that
+    // was pinned and never edited
+    fn message() -> Message {
+        fixtures::message(json!({
+            "edited_timestamp": null,
+            "mention_roles": ["41771983423143936"],
+            "pinned": true,
+            "position": 3
+        }))
+    }
+    
+    #[test]
+    fn typed_fields() {
+        let msg = fixtures::message(json!({}));
+        
+        assert!(!msg.tts && !msg.mention_everyone && !msg.pinned);
+        assert_eq!(msg.message_type, 19);
+        assert_eq!(msg.nonce, Some(Nonce::String("1162456786121195520".into())));
+        assert_eq!(msg.webhook_id, None);
+        assert_eq!(msg.attachments[0].filename, "cat.png");
+        assert_eq!(msg.embeds[0].title.as_deref(), Some("Cats"));
+        assert_eq!(msg.reactions[0].count, 2);
+        assert_eq!(msg.reactions[0].reaction_type(), ReactionType::Unicode("\u{1F44D}".into()));
+        assert_eq!(msg.reactions[2].emoji.animated, Some(false));
+        assert_eq!(msg.reactions[2].emoji.extra["roles"], json!([]));
+        assert_eq!(msg.reactions[2].reaction_type().to_string(), "<:catstare:1105891587839529042>");
+        assert_eq!(msg.message_reference.unwrap().message_id, Some(MessageId(Snowflake(1162456500000000000))));
+        assert!(matches!(msg.referenced_message, Some(None)));
+        assert_eq!(msg.components[0].component_type, ComponentType::ActionRow);
+        assert_eq!(msg.components[0].components[0].custom_id.as_deref(), Some("pet"));
+        assert_eq!(msg.sticker_items[0].name, "Wave");
+    }
+    
+    fn update(fields: serde_json::Value) -> MessageUpdate {
+        let mut update = json!({
+            "id": "1162456787912429669",
+            "channel_id": "381870553235193857"
+        });
+        update.as_object_mut().unwrap().extend(fields.as_object().unwrap().clone());
+        serde_json::from_value(update).unwrap()
+    }
+    
+    #[test]
+    fn absent_fields_are_untouched() {
+        let mut msg = message();
+        msg.apply_update(update(json!({})));
+        
+        assert_eq!(msg.content, "look at this");
+        assert_eq!(msg.guild_id, Some(GuildId(Snowflake(381870553235193856))));
+        assert_eq!(msg.mention_roles, vec![RoleId(Snowflake(41771983423143936))]);
+        assert!(msg.pinned);
+        assert!(msg.edited_timestamp.is_none());
+        assert!(msg.message_reference.is_some());
+        assert_eq!(msg.nonce, Some(Nonce::String("1162456786121195520".into())));
+        assert_eq!(msg.extra["channel_type"], 0);
+        assert_eq!(msg.extra["position"], 3);
+    }
+    
+    #[test]
+    fn present_fields_are_applied() {
+        let mut msg = message();
+        msg.apply_update(update(json!({
+            "content": "look at this cat",
+            "edited_timestamp": "2023-10-13T14:04:45.302000+00:00",
+            "pinned": false,
+            "mention_roles": [],
+            "nonce": 7,
+            "position": 4,
+            "poll": {"question": {"text": "cats?"}}
+        })));
+        
+        assert_eq!(msg.content, "look at this cat");
+        assert_eq!(msg.edited_timestamp.unwrap().to_string(), "2023-10-13T14:04:45.302000+00:00");
+        assert!(!msg.pinned);
+        assert!(msg.mention_roles.is_empty());
+        assert_eq!(msg.nonce, Some(Nonce::Int(7)));
+        // unmodeled fields are merged, not replaced as a whole
+        assert_eq!(msg.extra["channel_type"], 0);
+        assert_eq!(msg.extra["position"], 4);
+        assert_eq!(msg.extra["poll"]["question"]["text"], "cats?");
+    }
+    
+    #[test]
+    fn explicit_nulls_clear_fields() {
+        let mut msg = message();
+        msg.edited_timestamp = Some("2023-10-13T14:03:02.101000+00:00".parse().unwrap());
+        msg.apply_update(update(json!({
+            "edited_timestamp": null,
+            "message_reference": null,
+            "nonce": null
+        })));
+        
+        assert!(msg.edited_timestamp.is_none());
+        assert!(msg.message_reference.is_none());
+        assert!(msg.nonce.is_none());
+    }
+}
@@ -0,0 +1,331 @@
+
+use serde::{
+    Serializer,
+    Deserialize, Deserializer,
+};
+
+/// Declares an enum discord encodes as an integer, `u8` unless another type is
+/// given. Values this library doesn't know yet are kept in `Unknown` so they
+/// serialize back unchanged.
+macro_rules! int_enum {
+    ($(#[$meta:meta])* pub enum $name:ident { $($body:tt)* }) => {
+        int_enum! { $(#[$meta])* pub enum $name: u8 { $($body)* } }
+    };
+    ($(#[$meta:meta])* pub enum $name:ident: $repr:ident { $($(#[$vmeta:meta])* $variant:ident = $value:literal,)* }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($(#[$vmeta])* $variant,)*
+            Unknown($repr),
+        }
+        
+        impl From<$repr> for $name {
+            fn from(value: $repr) -> Self {
+                match value {
+                    $($value => $name::$variant,)*
+                    other => $name::Unknown(other),
+                }
+            }
+        }
+        
+        impl From<$name> for $repr {
+            fn from(value: $name) -> Self {
+                match value {
+                    $($name::$variant => $value,)*
+                    $name::Unknown(other) => other,
+                }
+            }
+        }
+        
+        impl serde::Serialize for $name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where S: serde::Serializer
+            {
+                serde::Serialize::serialize(&$repr::from(*self), serializer)
+            }
+        }
+        
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                where D: serde::Deserializer<'de>
+            {
+                <$repr as serde::Deserialize>::deserialize(deserializer).map($name::from)
+            }
+        }
+    };
+}
+
+mod timestamp;
+pub use self::timestamp::{Timestamp, TimestampParseError};
+
+mod snowflake;
+pub use self::snowflake::{Snowflake, DISCORD_EPOCH};
+
+mod id;
+pub use self::id::{GuildId, ChannelId, UserId, RoleId, MessageId};
+
+mod permissions;
+pub use self::permissions::{Permissions, base_permissions, overwrite_permissions};
+
+pub mod cdn;
+pub use self::cdn::{ImageFormat, ImageOptions};
+
+mod api_error;
+pub use self::api_error::{ApiError, ErrorCode, FieldError};
+
+mod channel;
+pub use self::channel::{
+    Channel,
+    ChannelType,
+    Overwrite,
+    OverwriteType,
+    ThreadMetadata,
+};
+
+mod guild;
+pub use self::guild::{
+    Guild,
+    VoiceState,
+    Sticker,
+    StickerType,
+};
+
+mod emoji;
+pub use self::emoji::{Emoji, ReactionType, ReactionTypeParseError};
+
+mod presence;
+pub use self::presence::{
+    Presence,
+    PresenceUser,
+    Status,
+    ClientStatus,
+    Activity,
+    ActivityType,
+    ActivityTimestamps,
+    ActivityEmoji,
+    ActivityParty,
+    ActivityAssets,
+};
+
+mod message;
+pub use self::message::{
+    Message,
+    MessageUpdate,
+    Nonce,
+    Attachment,
+    Embed,
+    EmbedFooter,
+    EmbedImage,
+    EmbedVideo,
+    EmbedProvider,
+    EmbedAuthor,
+    EmbedField,
+    Reaction,
+    PartialEmoji,
+    MessageActivity,
+    MessageApplication,
+    MessageReference,
+    MessageInteraction,
+    StickerItem,
+    StickerFormatType,
+    Component,
+    ComponentType,
+    SelectOption,
+};
+
+#[cfg(test)]
+pub(crate) mod fixtures;
+
+pub(crate) fn double_option<'de, T, D>(de: D) -> Result<Option<Option<T>>, D::Error>
+    where T: Deserialize<'de>,
+          D: Deserializer<'de>
+{
+    Deserialize::deserialize(de).map(Some)
+}
+
+/// Fields of a payload that aren't modeled, kept so it serializes back
+/// unchanged.
+///
+/// Models are flattened over this, which needs a self-describing format such
+/// as JSON. Ids, `Permissions` and `Timestamp` also work with binary formats
+/// like bincode, the models holding an `extra` don't.
+pub type Extra = serde_json::Map<String, serde_json::Value>;
+
+pub(crate) fn is_false(x: &bool) -> bool {
+    !*x
+}
+
+// for fields like `premium_subscriber` that are `null` when set and absent otherwise
+fn de_null_flag<'de, D>(de: D) -> Result<bool, D::Error>
+    where D: Deserializer<'de>
+{
+    <()>::deserialize(de).map(|_| true)
+}
+
+fn ser_null_flag<S>(_: &bool, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer
+{
+    serializer.serialize_unit()
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct User {
+    pub id: UserId,
+    pub username: String,
+    pub discriminator: String,
+    pub global_name: Option<String>,
+    pub avatar: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bot: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system: Option<bool>,
+    /// Only sent when fetching the user, `Some(None)` if they have no banner.
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub banner: Option<Option<String>>,
+    /// Only sent when fetching the user, `Some(None)` if they have no accent color.
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub accent_color: Option<Option<u32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_flags: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+impl User {
+    pub fn is_bot(&self) -> bool {
+        self.bot.unwrap_or(false)
+    }
+    
+    pub fn is_system(&self) -> bool {
+        self.system.unwrap_or(false)
+    }
+    
+    /// The global display name, falling back to the username.
+    pub fn display_name(&self) -> &str {
+        self.global_name.as_deref().unwrap_or(&self.username)
+    }
+    
+    pub fn avatar_url(&self, options: ImageOptions) -> Option<String> {
+        self.avatar.as_ref().map(|hash| cdn::user_avatar(self.id, hash, options))
+    }
+    
+    pub fn default_avatar_url(&self) -> String {
+        cdn::default_avatar(self.id, &self.discriminator)
+    }
+    
+    /// The avatar discord shows for this user, their own or the default one.
+    pub fn display_avatar_url(&self, options: ImageOptions) -> String {
+        self.avatar_url(options).unwrap_or_else(|| self.default_avatar_url())
+    }
+    
+    pub fn banner_url(&self, options: ImageOptions) -> Option<String> {
+        self.banner.as_ref()
+            .and_then(|hash| hash.as_ref())
+            .map(|hash| cdn::user_banner(self.id, hash, options))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Role {
+    pub id: RoleId,
+    pub name: String,
+    pub color: u32,
+    pub hoist: bool,
+    pub icon: Option<String>,
+    pub unicode_emoji: Option<String>,
+    pub position: usize,
+    pub permissions: Permissions,
+    pub managed: bool,
+    pub mentionable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<RoleTags>,
+    #[serde(default)]
+    pub flags: u64,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoleTags {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bot_id: Option<UserId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub integration_id: Option<Snowflake>,
+    #[serde(default, deserialize_with = "de_null_flag", serialize_with = "ser_null_flag", skip_serializing_if = "is_false")]
+    pub premium_subscriber: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subscription_listing_id: Option<Snowflake>,
+    #[serde(default, deserialize_with = "de_null_flag", serialize_with = "ser_null_flag", skip_serializing_if = "is_false")]
+    pub available_for_purchase: bool,
+    #[serde(default, deserialize_with = "de_null_flag", serialize_with = "ser_null_flag", skip_serializing_if = "is_false")]
+    pub guild_connections: bool,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuildMember {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<User>,
+    /// Left out of partial members, `Some(None)` if they have no nickname.
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub nick: Option<Option<String>>,
+    /// Left out of partial members, `Some(None)` if they have no guild avatar.
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<Option<String>>,
+    pub roles: Vec<RoleId>,
+    /// Left out of partial members, `Some(None)` for guest members.
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub joined_at: Option<Option<Timestamp>>,
+    /// Left out of partial members, `Some(None)` if they aren't boosting.
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub premium_since: Option<Option<Timestamp>>,
+    pub deaf: bool,
+    pub mute: bool,
+    #[serde(default)]
+    pub flags: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pending: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<Permissions>,
+    /// Left out of partial members, `Some(None)` if they aren't timed out.
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub communication_disabled_until: Option<Option<Timestamp>>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnavailableGuild {
+    pub id: GuildId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unavailable: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeletedMessage {
+    pub id: MessageId,
+    pub channel_id: ChannelId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guild_id: Option<GuildId>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreatedRole {
+    pub guild_id: GuildId,
+    pub role: Role,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeletedRole {
+    pub guild_id: GuildId,
+    pub role_id: RoleId,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
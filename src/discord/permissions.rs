@@ -0,0 +1,455 @@
+
+use std::fmt;
+use std::str::FromStr;
+
+use bitflags::bitflags;
+use serde::{
+    Serialize, Serializer,
+    Deserialize, Deserializer,
+};
+use serde::de;
+
+use super::{
+    Guild,
+    GuildId,
+    UserId,
+    RoleId,
+    Role,
+    GuildMember,
+    Channel,
+    Overwrite,
+    OverwriteType,
+    Timestamp,
+};
+
+bitflags! {
+    /// Permission bits for roles, overwrites and members. Bits without a
+    /// name here are kept rather than dropped.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+    pub struct Permissions: u64 {
+        const CREATE_INSTANT_INVITE = 1 << 0;
+        const KICK_MEMBERS = 1 << 1;
+        const BAN_MEMBERS = 1 << 2;
+        const ADMINISTRATOR = 1 << 3;
+        const MANAGE_CHANNELS = 1 << 4;
+        const MANAGE_GUILD = 1 << 5;
+        const ADD_REACTIONS = 1 << 6;
+        const VIEW_AUDIT_LOG = 1 << 7;
+        const PRIORITY_SPEAKER = 1 << 8;
+        const STREAM = 1 << 9;
+        const VIEW_CHANNEL = 1 << 10;
+        const SEND_MESSAGES = 1 << 11;
+        const SEND_TTS_MESSAGES = 1 << 12;
+        const MANAGE_MESSAGES = 1 << 13;
+        const EMBED_LINKS = 1 << 14;
+        const ATTACH_FILES = 1 << 15;
+        const READ_MESSAGE_HISTORY = 1 << 16;
+        const MENTION_EVERYONE = 1 << 17;
+        const USE_EXTERNAL_EMOJIS = 1 << 18;
+        const VIEW_GUILD_INSIGHTS = 1 << 19;
+        const CONNECT = 1 << 20;
+        const SPEAK = 1 << 21;
+        const MUTE_MEMBERS = 1 << 22;
+        const DEAFEN_MEMBERS = 1 << 23;
+        const MOVE_MEMBERS = 1 << 24;
+        const USE_VAD = 1 << 25;
+        const CHANGE_NICKNAME = 1 << 26;
+        const MANAGE_NICKNAMES = 1 << 27;
+        const MANAGE_ROLES = 1 << 28;
+        const MANAGE_WEBHOOKS = 1 << 29;
+        const MANAGE_GUILD_EXPRESSIONS = 1 << 30;
+        const USE_APPLICATION_COMMANDS = 1 << 31;
+        const REQUEST_TO_SPEAK = 1 << 32;
+        const MANAGE_EVENTS = 1 << 33;
+        const MANAGE_THREADS = 1 << 34;
+        const CREATE_PUBLIC_THREADS = 1 << 35;
+        const CREATE_PRIVATE_THREADS = 1 << 36;
+        const USE_EXTERNAL_STICKERS = 1 << 37;
+        const SEND_MESSAGES_IN_THREADS = 1 << 38;
+        const USE_EMBEDDED_ACTIVITIES = 1 << 39;
+        const MODERATE_MEMBERS = 1 << 40;
+        const VIEW_CREATOR_MONETIZATION_ANALYTICS = 1 << 41;
+        const USE_SOUNDBOARD = 1 << 42;
+        const CREATE_GUILD_EXPRESSIONS = 1 << 43;
+        const CREATE_EVENTS = 1 << 44;
+        const USE_EXTERNAL_SOUNDS = 1 << 45;
+        const SEND_VOICE_MESSAGES = 1 << 46;
+        const SET_VOICE_CHANNEL_STATUS = 1 << 48;
+        const SEND_POLLS = 1 << 49;
+        const USE_EXTERNAL_APPS = 1 << 50;
+    }
+}
+
+impl Permissions {
+    pub fn is_admin(&self) -> bool {
+        self.contains(Permissions::ADMINISTRATOR)
+    }
+}
+
+/// Lists the set permission names separated by ` | `, with any unnamed bits
+/// as a trailing hex value.
+impl fmt::Display for Permissions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        bitflags::parser::to_writer(self, f)
+    }
+}
+
+/// Parses the decimal string form discord uses.
+impl FromStr for Permissions {
+    type Err = std::num::ParseIntError;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Permissions::from_bits_retain)
+    }
+}
+
+impl Serialize for Permissions {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(&self.bits())
+    }
+}
+
+struct PermissionsVisitor;
+
+impl<'de> de::Visitor<'de> for PermissionsVisitor {
+    type Value = Permissions;
+    
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a string representing a permission bitset")
+    }
+    
+    fn visit_str<E>(self, v: &str) -> Result<Permissions, E>
+        where E: de::Error
+    {
+        v.parse().map_err(|_| {
+            E::invalid_value(de::Unexpected::Str(v), &self)
+        })
+    }
+    
+    fn visit_u64<E>(self, v: u64) -> Result<Permissions, E>
+        where E: de::Error
+    {
+        Ok(Permissions::from_bits_retain(v))
+    }
+}
+
+impl<'de> Deserialize<'de> for Permissions {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        // as with snowflakes, only self-describing formats can be asked for
+        // either an int or a string
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(PermissionsVisitor)
+        } else {
+            deserializer.deserialize_str(PermissionsVisitor)
+        }
+    }
+}
+
+/// Guild wide permissions of a member, before any channel overwrites.
+pub fn base_permissions(
+    guild_id: GuildId,
+    owner_id: UserId,
+    roles: &[Role],
+    user_id: UserId,
+    member_roles: &[RoleId],
+    ) -> Permissions
+{
+    if user_id == owner_id {
+        return Permissions::all()
+    }
+    
+    // the @everyone role shares the guild's id
+    let mut permissions = roles.iter()
+        .filter(|role| role.id.0 == guild_id.0 || member_roles.contains(&role.id))
+        .fold(Permissions::empty(), |acc, role| acc | role.permissions);
+    
+    if permissions.is_admin() {
+        permissions = Permissions::all()
+    }
+    
+    permissions
+}
+
+/// Applies a channel's overwrites to a member's base permissions in the order
+/// discord does: @everyone, then all of the member's roles together, then the
+/// member itself.
+pub fn overwrite_permissions(
+    base: Permissions,
+    guild_id: GuildId,
+    user_id: UserId,
+    member_roles: &[RoleId],
+    overwrites: &[Overwrite],
+    ) -> Permissions
+{
+    if base.is_admin() {
+        return Permissions::all()
+    }
+    
+    let mut permissions = base;
+    
+    let everyone = overwrites.iter()
+        .find(|o| matches!(o.overwrite_type, OverwriteType::Role) && o.id == guild_id.0);
+    if let Some(overwrite) = everyone {
+        permissions &= !overwrite.deny;
+        permissions |= overwrite.allow;
+    }
+    
+    let (allow, deny) = overwrites.iter()
+        .filter(|o| matches!(o.overwrite_type, OverwriteType::Role))
+        .filter(|o| member_roles.iter().any(|role| role.0 == o.id))
+        .fold((Permissions::empty(), Permissions::empty()), |(allow, deny), o| {
+            (allow | o.allow, deny | o.deny)
+        });
+    permissions &= !deny;
+    permissions |= allow;
+    
+    let member = overwrites.iter()
+        .find(|o| matches!(o.overwrite_type, OverwriteType::Member) && o.id == user_id.0);
+    if let Some(overwrite) = member {
+        permissions &= !overwrite.deny;
+        permissions |= overwrite.allow;
+    }
+    
+    // implicit permissions
+    if !permissions.contains(Permissions::VIEW_CHANNEL) {
+        return Permissions::empty()
+    }
+    if !permissions.contains(Permissions::SEND_MESSAGES) {
+        permissions &= !(Permissions::SEND_TTS_MESSAGES
+            | Permissions::MENTION_EVERYONE
+            | Permissions::EMBED_LINKS
+            | Permissions::ATTACH_FILES);
+    }
+    
+    permissions
+}
+
+// members in timeout can only read
+fn apply_timeout(permissions: Permissions, member: &GuildMember) -> Permissions {
+    let timed_out = member.communication_disabled_until
+        .flatten()
+        .is_some_and(|until| until > Timestamp::now());
+    
+    if timed_out && !permissions.is_admin() {
+        permissions & (Permissions::VIEW_CHANNEL | Permissions::READ_MESSAGE_HISTORY)
+    } else {
+        permissions
+    }
+}
+
+impl Guild {
+    /// Guild wide permissions of a member. `user_id` is passed separately as
+    /// members attached to messages don't include their user.
+    pub fn member_permissions(&self, user_id: UserId, member: &GuildMember) -> Permissions {
+        let base = base_permissions(self.id, self.owner_id, &self.roles, user_id, &member.roles);
+        apply_timeout(base, member)
+    }
+    
+    /// Effective permissions of a member in one of this guild's channels.
+    pub fn channel_permissions(&self, user_id: UserId, member: &GuildMember, channel: &Channel) -> Permissions {
+        let base = base_permissions(self.id, self.owner_id, &self.roles, user_id, &member.roles);
+        let overwrites = channel.permission_overwrites.as_deref().unwrap_or(&[]);
+        let permissions = overwrite_permissions(base, self.id, user_id, &member.roles, overwrites);
+        apply_timeout(permissions, member)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    use crate::discord::{fixtures, Role};
+    
+    const GUILD: u64 = 100;
+    const OWNER: u64 = 1;
+    const USER: u64 = 2;
+    const MODS: u64 = 10;
+    const ADMINS: u64 = 11;
+    const MUTED: u64 = 12;
+    
+    fn perms(p: Permissions) -> String {
+        p.bits().to_string()
+    }
+    
+    fn role(id: u64, position: usize, permissions: Permissions) -> Role {
+        fixtures::role(json!({
+            "id": id.to_string(),
+            "position": position,
+            "permissions": perms(permissions),
+        }))
+    }
+    
+    fn guild(everyone: Permissions) -> Guild {
+        let mut guild = fixtures::guild(json!({
+            "id": GUILD.to_string(),
+            "owner_id": OWNER.to_string(),
+        }));
+        guild.roles = vec![
+            role(GUILD, 0, everyone),
+            role(MODS, 1, Permissions::MANAGE_MESSAGES),
+            role(ADMINS, 2, Permissions::ADMINISTRATOR),
+            role(MUTED, 3, Permissions::empty()),
+        ];
+        guild
+    }
+    
+    fn member(roles: &[u64], timeout: Option<&str>) -> GuildMember {
+        let roles: Vec<String> = roles.iter().map(|r| r.to_string()).collect();
+        fixtures::member(json!({
+            "roles": roles,
+            "communication_disabled_until": timeout,
+        }))
+    }
+    
+    fn channel(overwrites: serde_json::Value) -> Channel {
+        fixtures::channel(json!({
+            "guild_id": GUILD.to_string(),
+            "permission_overwrites": overwrites,
+        }))
+    }
+    
+    fn overwrite(id: u64, kind: u8, allow: Permissions, deny: Permissions) -> serde_json::Value {
+        json!({"id": id.to_string(), "type": kind, "allow": perms(allow), "deny": perms(deny)})
+    }
+    
+    fn everyone() -> Permissions {
+        Permissions::VIEW_CHANNEL | Permissions::SEND_MESSAGES | Permissions::READ_MESSAGE_HISTORY
+    }
+    
+    #[test]
+    fn owner_has_everything() {
+        let guild = guild(Permissions::empty());
+        let channel = channel(json!([overwrite(GUILD, 0, Permissions::empty(), Permissions::all())]));
+        
+        let p = guild.channel_permissions(UserId::from(OWNER), &member(&[], None), &channel);
+        assert_eq!(p, Permissions::all());
+    }
+    
+    #[test]
+    fn everyone_and_roles_combine() {
+        let guild = guild(everyone());
+        
+        let p = guild.member_permissions(UserId::from(USER), &member(&[], None));
+        assert_eq!(p, everyone());
+        
+        let p = guild.member_permissions(UserId::from(USER), &member(&[MODS], None));
+        assert_eq!(p, everyone() | Permissions::MANAGE_MESSAGES);
+    }
+    
+    #[test]
+    fn administrator_ignores_overwrites() {
+        let guild = guild(everyone());
+        let channel = channel(json!([
+            overwrite(GUILD, 0, Permissions::empty(), Permissions::VIEW_CHANNEL),
+            overwrite(USER, 1, Permissions::empty(), Permissions::all()),
+        ]));
+        
+        let p = guild.channel_permissions(UserId::from(USER), &member(&[ADMINS], None), &channel);
+        assert_eq!(p, Permissions::all());
+        assert!(p.is_admin());
+    }
+    
+    #[test]
+    fn role_overwrite_beats_everyone_overwrite() {
+        let guild = guild(everyone());
+        let channel = channel(json!([
+            overwrite(GUILD, 0, Permissions::empty(), Permissions::SEND_MESSAGES),
+            overwrite(MODS, 0, Permissions::SEND_MESSAGES, Permissions::empty()),
+        ]));
+        
+        let p = guild.channel_permissions(UserId::from(USER), &member(&[], None), &channel);
+        assert!(!p.contains(Permissions::SEND_MESSAGES));
+        
+        let p = guild.channel_permissions(UserId::from(USER), &member(&[MODS], None), &channel);
+        assert!(p.contains(Permissions::SEND_MESSAGES));
+    }
+    
+    #[test]
+    fn role_allow_beats_role_deny() {
+        let guild = guild(everyone());
+        let channel = channel(json!([
+            overwrite(MUTED, 0, Permissions::empty(), Permissions::SEND_MESSAGES),
+            overwrite(MODS, 0, Permissions::SEND_MESSAGES, Permissions::empty()),
+        ]));
+        
+        let p = guild.channel_permissions(UserId::from(USER), &member(&[MUTED], None), &channel);
+        assert!(!p.contains(Permissions::SEND_MESSAGES));
+        
+        let p = guild.channel_permissions(UserId::from(USER), &member(&[MUTED, MODS], None), &channel);
+        assert!(p.contains(Permissions::SEND_MESSAGES));
+    }
+    
+    #[test]
+    fn member_overwrite_beats_role_overwrite() {
+        let guild = guild(everyone());
+        let channel = channel(json!([
+            overwrite(MODS, 0, Permissions::ADD_REACTIONS, Permissions::empty()),
+            overwrite(USER, 1, Permissions::empty(), Permissions::ADD_REACTIONS | Permissions::SEND_MESSAGES),
+        ]));
+        
+        let p = guild.channel_permissions(UserId::from(USER), &member(&[MODS], None), &channel);
+        assert!(!p.contains(Permissions::ADD_REACTIONS));
+        assert!(!p.contains(Permissions::SEND_MESSAGES));
+        assert!(p.contains(Permissions::VIEW_CHANNEL));
+    }
+    
+    #[test]
+    fn hidden_channel_grants_nothing() {
+        let guild = guild(everyone() | Permissions::ADD_REACTIONS);
+        let channel = channel(json!([
+            overwrite(GUILD, 0, Permissions::empty(), Permissions::VIEW_CHANNEL),
+        ]));
+        
+        let p = guild.channel_permissions(UserId::from(USER), &member(&[MODS], None), &channel);
+        assert_eq!(p, Permissions::empty());
+    }
+    
+    #[test]
+    fn no_send_removes_send_dependent() {
+        let guild = guild(everyone() | Permissions::EMBED_LINKS | Permissions::ATTACH_FILES);
+        let channel = channel(json!([
+            overwrite(GUILD, 0, Permissions::empty(), Permissions::SEND_MESSAGES),
+        ]));
+        
+        let p = guild.channel_permissions(UserId::from(USER), &member(&[], None), &channel);
+        assert_eq!(p, Permissions::VIEW_CHANNEL | Permissions::READ_MESSAGE_HISTORY);
+    }
+    
+    #[test]
+    fn timeout_only_allows_reading() {
+        let guild = guild(everyone() | Permissions::ADD_REACTIONS);
+        let channel = channel(json!([]));
+        
+        let timed_out = member(&[MODS], Some("2999-01-01T00:00:00.000000+00:00"));
+        let p = guild.channel_permissions(UserId::from(USER), &timed_out, &channel);
+        assert_eq!(p, Permissions::VIEW_CHANNEL | Permissions::READ_MESSAGE_HISTORY);
+        
+        let expired = member(&[MODS], Some("2001-01-01T00:00:00.000000+00:00"));
+        let p = guild.channel_permissions(UserId::from(USER), &expired, &channel);
+        assert!(p.contains(Permissions::ADD_REACTIONS | Permissions::MANAGE_MESSAGES));
+        
+        let admin = member(&[ADMINS], Some("2999-01-01T00:00:00.000000+00:00"));
+        let p = guild.channel_permissions(UserId::from(USER), &admin, &channel);
+        assert_eq!(p, Permissions::all());
+    }
+    
+    #[test]
+    fn permissions_string_round_trip() {
+        let p: Permissions = serde_json::from_value(json!("2147483648")).unwrap();
+        assert_eq!(p, Permissions::USE_APPLICATION_COMMANDS);
+        assert_eq!(serde_json::to_value(p).unwrap(), json!("2147483648"));
+        assert_eq!((Permissions::VIEW_CHANNEL | Permissions::SEND_MESSAGES).to_string(), "VIEW_CHANNEL | SEND_MESSAGES");
+        
+        let p: Permissions = serde_json::from_value(json!("281474976710656")).unwrap();
+        assert_eq!(p, Permissions::SET_VOICE_CHANNEL_STATUS);
+        
+        let bytes = bincode::serialize(&p).unwrap();
+        assert_eq!(bincode::deserialize::<Permissions>(&bytes).unwrap(), p);
+    }
+}
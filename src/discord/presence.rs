@@ -0,0 +1,222 @@
+
+use std::fmt;
+
+use serde::{
+    Serialize, Serializer,
+    Deserialize, Deserializer,
+};
+
+use super::{
+    Extra,
+    double_option,
+    Snowflake,
+    GuildId,
+    UserId,
+};
+
+/// The user of a presence. Only `id` is always sent, the other fields are
+/// there when they changed. `global_name` and `avatar` are `Some(None)` when
+/// they were removed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresenceUser {
+    pub id: UserId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub discriminator: Option<String>,
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub global_name: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<Option<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bot: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_flags: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// A member's presence, sent in GUILD_CREATE and as PRESENCE_UPDATE.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Presence {
+    pub user: PresenceUser,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guild_id: Option<GuildId>,
+    pub status: Status,
+    #[serde(default)]
+    pub activities: Vec<Activity>,
+    #[serde(default)]
+    pub client_status: ClientStatus,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Status {
+    Online,
+    Idle,
+    Dnd,
+    /// Users appearing offline are also sent as `Offline`.
+    Offline,
+    Invisible,
+    Unknown(String),
+}
+
+impl Status {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Status::Online => "online",
+            Status::Idle => "idle",
+            Status::Dnd => "dnd",
+            Status::Offline => "offline",
+            Status::Invisible => "invisible",
+            Status::Unknown(status) => status,
+        }
+    }
+}
+
+impl From<String> for Status {
+    fn from(status: String) -> Self {
+        match status.as_str() {
+            "online" => Status::Online,
+            "idle" => Status::Idle,
+            "dnd" => Status::Dnd,
+            "offline" => Status::Offline,
+            "invisible" => Status::Invisible,
+            _ => Status::Unknown(status),
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for Status {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Status {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        String::deserialize(deserializer).map(Status::from)
+    }
+}
+
+/// Status per platform, a platform is `None` when the user isn't active on it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClientStatus {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub desktop: Option<Status>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mobile: Option<Status>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub web: Option<Status>,
+    /// Consoles and other devices running discord inside another app.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embedded: Option<Status>,
+}
+
+int_enum! {
+    pub enum ActivityType {
+        Playing = 0,
+        Streaming = 1,
+        Listening = 2,
+        Watching = 3,
+        Custom = 4,
+        Competing = 5,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Activity {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub activity_type: ActivityType,
+    /// Discord's own identifier for the activity, `"custom"` for custom statuses.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Unix timestamp in milliseconds.
+    pub created_at: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamps: Option<ActivityTimestamps>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub application_id: Option<Snowflake>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+    /// The text of a custom status.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub emoji: Option<ActivityEmoji>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub party: Option<ActivityParty>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assets: Option<ActivityAssets>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flags: Option<u64>,
+    /// Bots only receive the button labels.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub buttons: Vec<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// Unix timestamps in milliseconds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityTimestamps {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityEmoji {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<Snowflake>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub animated: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityParty {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Current and maximum size.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<[u64; 2]>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityAssets {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub large_image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub large_text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub small_image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub small_text: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
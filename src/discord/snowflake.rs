@@ -0,0 +1,219 @@
+
+use std::fmt;
+use std::num::ParseIntError;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{
+    Serialize, Serializer,
+    Deserialize, Deserializer,
+};
+use serde::de;
+
+use super::Timestamp;
+
+/// Milliseconds since the unix epoch at the start of 2015, the zero point of
+/// snowflake timestamps.
+pub const DISCORD_EPOCH: u64 = 1420070400000;
+
+// the timestamp takes the top 42 bits
+const MAX_TIMESTAMP: u64 = (1 << 42) - 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Snowflake(pub u64);
+
+impl std::ops::Deref for Snowflake {
+    type Target = u64;
+    
+    fn deref(&self) -> &Self::Target {
+        let Snowflake(x) = self;
+        x
+    }
+}
+
+impl Snowflake {
+    /// The smallest snowflake that could have been created at `unix_millis`,
+    /// useful as a `before`/`after` bound when paginating by time. Times after
+    /// the last one a snowflake can hold, in 2154, give that time's snowflake.
+    pub fn from_timestamp_millis(unix_millis: u64) -> Self {
+        Snowflake(unix_millis.saturating_sub(DISCORD_EPOCH).min(MAX_TIMESTAMP) << 22)
+    }
+    
+    pub fn from_time(time: SystemTime) -> Self {
+        Snowflake::from_timestamp_millis(unix_millis(time))
+    }
+    
+    /// The largest snowflake that could have been created at `unix_millis`,
+    /// with the worker, process and increment bits all set. Together with
+    /// `from_timestamp_millis` it bounds every id from that millisecond.
+    pub fn max_for_timestamp_millis(unix_millis: u64) -> Self {
+        Snowflake(Snowflake::from_timestamp_millis(unix_millis).0 | 0x3FFFFF)
+    }
+    
+    pub fn max_for_time(time: SystemTime) -> Self {
+        Snowflake::max_for_timestamp_millis(unix_millis(time))
+    }
+    
+    /// Creation time in milliseconds since the unix epoch.
+    pub fn timestamp_millis(&self) -> u64 {
+        (self.0 >> 22) + DISCORD_EPOCH
+    }
+    
+    pub fn created_at(&self) -> Timestamp {
+        (UNIX_EPOCH + Duration::from_millis(self.timestamp_millis())).into()
+    }
+    
+    pub fn worker_id(&self) -> u8 {
+        ((self.0 & 0x3E0000) >> 17) as u8
+    }
+    
+    pub fn process_id(&self) -> u8 {
+        ((self.0 & 0x1F000) >> 12) as u8
+    }
+    
+    pub fn increment(&self) -> u16 {
+        (self.0 & 0xFFF) as u16
+    }
+}
+
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+impl From<u64> for Snowflake {
+    fn from(id: u64) -> Self {
+        Snowflake(id)
+    }
+}
+
+impl fmt::Display for Snowflake {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for Snowflake {
+    type Err = ParseIntError;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Snowflake)
+    }
+}
+
+impl Serialize for Snowflake {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+struct SnowflakeVisitor;
+
+impl<'de> de::Visitor<'de> for SnowflakeVisitor {
+    type Value = Snowflake;
+    
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a 64-bit int or a string representing one")
+    }
+    
+    fn visit_str<E>(self, v: &str) -> Result<Snowflake, E>
+        where E: de::Error
+    {
+        v.parse().map_err(|_| {
+            E::invalid_value(de::Unexpected::Str(v), &self)
+        })
+    }
+    
+    fn visit_u64<E>(self, v: u64) -> Result<Snowflake, E>
+        where E: de::Error
+    {
+        Ok(Snowflake(v))
+    }
+    
+    fn visit_i64<E>(self, v: i64) -> Result<Snowflake, E>
+        where E: de::Error
+    {
+        u64::try_from(v).map(Snowflake).map_err(|_| {
+            E::invalid_value(de::Unexpected::Signed(v), &self)
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Snowflake {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> 
+        where D: Deserializer<'de>
+    {
+        // discord sends some ids as ints, but formats that aren't
+        // self-describing can only be asked for the string we serialize
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(SnowflakeVisitor)
+        } else {
+            deserializer.deserialize_str(SnowflakeVisitor)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    // created 2016-04-30 11:18:25.796 UTC by worker 1, process 0, increment 7
+    const ID: Snowflake = Snowflake(175928847299117063);
+    
+    #[test]
+    fn fields() {
+        assert_eq!(ID.timestamp_millis(), 1462015105796);
+        assert_eq!(ID.worker_id(), 1);
+        assert_eq!(ID.process_id(), 0);
+        assert_eq!(ID.increment(), 7);
+        assert_eq!(ID.created_at().to_string(), "2016-04-30T11:18:25.796000+00:00");
+        
+        let all = Snowflake(u64::MAX);
+        assert_eq!(all.worker_id(), 31);
+        assert_eq!(all.process_id(), 31);
+        assert_eq!(all.increment(), 4095);
+    }
+    
+    #[test]
+    fn time_bounds() {
+        let millis = ID.timestamp_millis();
+        let min = Snowflake::from_timestamp_millis(millis);
+        let max = Snowflake::max_for_timestamp_millis(millis);
+        
+        assert!(min <= ID && ID <= max);
+        assert_eq!((min.timestamp_millis(), max.timestamp_millis()), (millis, millis));
+        assert_eq!((min.worker_id(), min.process_id(), min.increment()), (0, 0, 0));
+        assert_eq!((max.worker_id(), max.process_id(), max.increment()), (31, 31, 4095));
+        assert_eq!(Snowflake(max.0 + 1), Snowflake::from_timestamp_millis(millis + 1));
+        
+        let time = UNIX_EPOCH + Duration::from_millis(millis);
+        assert_eq!(Snowflake::from_time(time), min);
+        assert_eq!(Snowflake::max_for_time(time), max);
+        
+        // nothing is older than discord's epoch
+        assert_eq!(Snowflake::from_timestamp_millis(0), Snowflake(0));
+        assert_eq!(Snowflake::from_time(UNIX_EPOCH - Duration::from_secs(1)), Snowflake(0));
+        
+        // or newer than the 42 bit timestamp allows
+        let last = DISCORD_EPOCH + MAX_TIMESTAMP;
+        assert_eq!(Snowflake::max_for_timestamp_millis(last), Snowflake(u64::MAX));
+        assert_eq!(Snowflake::from_timestamp_millis(last + 1), Snowflake::from_timestamp_millis(last));
+        assert_eq!(Snowflake::max_for_timestamp_millis(u64::MAX), Snowflake(u64::MAX));
+    }
+    
+    #[test]
+    fn decoding() {
+        assert_eq!(serde_json::from_str::<Snowflake>(r#""175928847299117063""#).unwrap(), ID);
+        assert_eq!(serde_json::from_str::<Snowflake>("175928847299117063").unwrap(), ID);
+        assert!(serde_json::from_str::<Snowflake>("-1").is_err());
+        assert!(serde_json::from_str::<Snowflake>(r#""12ab""#).is_err());
+        assert_eq!(serde_json::to_string(&ID).unwrap(), r#""175928847299117063""#);
+        
+        let bytes = bincode::serialize(&ID).unwrap();
+        assert_eq!(bincode::deserialize::<Snowflake>(&bytes).unwrap(), ID);
+    }
+}
@@ -0,0 +1,409 @@
+#![allow(clippy::redundant_field_names)]
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{
+    Serialize, Serializer,
+    Deserialize, Deserializer,
+};
+use serde::de;
+
+/// An ISO 8601 timestamp as sent by discord, e.g. `2017-08-30T10:42:45.869000+00:00`.
+///
+/// The offset and number of fractional digits are remembered so the timestamp
+/// serializes back to the same string it was parsed from. Comparisons only
+/// look at the instant in time.
+#[derive(Debug, Clone, Copy)]
+pub struct Timestamp {
+    // seconds since the unix epoch in UTC
+    secs: i64,
+    nanos: u32,
+    // offset from UTC in seconds, None when written as `Z`
+    offset: Option<i32>,
+    // number of fractional second digits written
+    precision: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimestampParseError {
+    input: String,
+}
+
+impl fmt::Display for TimestampParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid ISO 8601 timestamp: {:?}", self.input)
+    }
+}
+
+impl std::error::Error for TimestampParseError {}
+
+// days since 1970-01-01 for a proleptic gregorian date
+fn days_from_civil(y: i64, m: u32, d: u32) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let mp = (m as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + d as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(z: i64) -> (i64, u32, u32) {
+    let z = z + 719468;
+    let era = if z >= 0 { z } else { z - 146096 } / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400;
+    (if m <= 2 { y + 1 } else { y }, m, d)
+}
+
+fn days_in_month(y: i64, m: u32) -> u32 {
+    match m {
+        2 if (y % 4 == 0 && y % 100 != 0) || y % 400 == 0 => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl Timestamp {
+    /// Seconds since the unix epoch.
+    pub fn unix_secs(&self) -> i64 {
+        self.secs
+    }
+    
+    /// Milliseconds since the unix epoch.
+    pub fn unix_millis(&self) -> i64 {
+        self.secs * 1000 + (self.nanos / 1_000_000) as i64
+    }
+    
+    pub fn subsec_nanos(&self) -> u32 {
+        self.nanos
+    }
+    
+    pub fn to_system_time(&self) -> SystemTime {
+        if self.secs >= 0 {
+            UNIX_EPOCH + Duration::new(self.secs as u64, self.nanos)
+        } else {
+            UNIX_EPOCH - Duration::from_secs(self.secs.unsigned_abs()) + Duration::from_nanos(self.nanos as u64)
+        }
+    }
+    
+    pub fn now() -> Self {
+        SystemTime::now().into()
+    }
+    
+    fn parse(s: &str) -> Option<Self> {
+        let b = s.as_bytes();
+        
+        let num = |range: std::ops::Range<usize>| -> Option<u32> {
+            let digits = b.get(range)?;
+            if !digits.iter().all(u8::is_ascii_digit) {
+                return None
+            }
+            digits.iter().try_fold(0u32, |acc, d| Some(acc * 10 + (d - b'0') as u32))
+        };
+        let sep = |i: usize, c: &[u8]| b.get(i).is_some_and(|x| c.contains(x));
+        
+        if !(sep(4, b"-") && sep(7, b"-") && sep(10, b"Tt ") && sep(13, b":") && sep(16, b":")) {
+            return None
+        }
+        
+        let year = num(0..4)? as i64;
+        let month = num(5..7)?;
+        let day = num(8..10)?;
+        let hour = num(11..13)?;
+        let minute = num(14..16)?;
+        let second = num(17..19)?;
+        
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month)
+            || hour > 23 || minute > 59 || second > 59 {
+            return None
+        }
+        
+        let mut i = 19;
+        let mut nanos = 0;
+        let mut precision = 0;
+        if sep(i, b".") {
+            i += 1;
+            let start = i;
+            while b.get(i).is_some_and(u8::is_ascii_digit) {
+                i += 1;
+            }
+            precision = i - start;
+            if precision == 0 || precision > 9 {
+                return None
+            }
+            nanos = num(start..i)? * 10u32.pow(9 - precision as u32);
+        }
+        
+        let offset = match b.get(i) {
+            Some(b'Z') | Some(b'z') if i + 1 == b.len() => None,
+            Some(sign @ (b'+' | b'-')) if i + 6 == b.len() && sep(i + 3, b":") => {
+                let hours = num(i + 1..i + 3)?;
+                let minutes = num(i + 4..i + 6)?;
+                if hours > 23 || minutes > 59 {
+                    return None
+                }
+                let offset = (hours * 3600 + minutes * 60) as i32;
+                Some(if *sign == b'-' { -offset } else { offset })
+            }
+            _ => return None,
+        };
+        
+        let local = days_from_civil(year, month, day) * 86400
+            + (hour * 3600 + minute * 60 + second) as i64;
+        
+        Some(Timestamp {
+            secs: local - offset.unwrap_or(0) as i64,
+            nanos: nanos,
+            offset: offset,
+            precision: precision as u8,
+        })
+    }
+}
+
+impl From<SystemTime> for Timestamp {
+    fn from(time: SystemTime) -> Self {
+        let (secs, nanos) = match time.duration_since(UNIX_EPOCH) {
+            Ok(d) => (d.as_secs() as i64, d.subsec_nanos()),
+            Err(err) => {
+                let d = err.duration();
+                match d.subsec_nanos() {
+                    0 => (-(d.as_secs() as i64), 0),
+                    n => (-(d.as_secs() as i64) - 1, 1_000_000_000 - n),
+                }
+            }
+        };
+        
+        // discord's own format
+        Timestamp {
+            secs: secs,
+            nanos: nanos - nanos % 1000,
+            offset: Some(0),
+            precision: 6,
+        }
+    }
+}
+
+impl From<Timestamp> for SystemTime {
+    fn from(timestamp: Timestamp) -> Self {
+        timestamp.to_system_time()
+    }
+}
+
+impl FromStr for Timestamp {
+    type Err = TimestampParseError;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Timestamp::parse(s).ok_or_else(|| TimestampParseError { input: s.into() })
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let local = self.secs + self.offset.unwrap_or(0) as i64;
+        let (year, month, day) = civil_from_days(local.div_euclid(86400));
+        let time = local.rem_euclid(86400);
+        
+        write!(f, "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            year, month, day, time / 3600, time % 3600 / 60, time % 60)?;
+        
+        if self.precision > 0 {
+            let frac = self.nanos / 10u32.pow(9 - self.precision as u32);
+            write!(f, ".{:0width$}", frac, width = self.precision as usize)?;
+        }
+        
+        match self.offset {
+            None => write!(f, "Z"),
+            Some(offset) => {
+                let sign = if offset < 0 { '-' } else { '+' };
+                let offset = offset.abs();
+                write!(f, "{}{:02}:{:02}", sign, offset / 3600, offset % 3600 / 60)
+            }
+        }
+    }
+}
+
+impl PartialEq for Timestamp {
+    fn eq(&self, other: &Self) -> bool {
+        (self.secs, self.nanos) == (other.secs, other.nanos)
+    }
+}
+
+impl Eq for Timestamp {}
+
+impl PartialOrd for Timestamp {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Timestamp {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.secs, self.nanos).cmp(&(other.secs, other.nanos))
+    }
+}
+
+impl Hash for Timestamp {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.secs, self.nanos).hash(state)
+    }
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+struct TimestampVisitor;
+
+impl<'de> de::Visitor<'de> for TimestampVisitor {
+    type Value = Timestamp;
+    
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "an ISO 8601 timestamp")
+    }
+    
+    fn visit_str<E>(self, v: &str) -> Result<Timestamp, E>
+        where E: de::Error
+    {
+        v.parse().map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        deserializer.deserialize_str(TimestampVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn ts(s: &str) -> Timestamp {
+        s.parse().unwrap()
+    }
+    
+    #[test]
+    fn offsets() {
+        let utc = ts("2017-08-30T10:42:45Z");
+        assert_eq!(utc.unix_secs(), 1504089765);
+        assert_eq!(ts("2017-08-30T12:42:45+02:00"), utc);
+        assert_eq!(ts("2017-08-30T05:12:45-05:30"), utc);
+        assert_eq!(ts("2017-08-30T05:12:45-05:30").to_string(), "2017-08-30T05:12:45-05:30");
+        
+        // crossing midnight and the year in local time
+        assert_eq!(ts("2016-12-31T23:30:00-01:00"), ts("2017-01-01T00:30:00Z"));
+    }
+    
+    #[test]
+    fn fractions() {
+        let zero = ts("2017-08-30T10:42:45+00:00");
+        assert_eq!(zero.subsec_nanos(), 0);
+        assert_eq!(zero.to_string(), "2017-08-30T10:42:45+00:00");
+        
+        let millis = ts("2017-08-30T10:42:45.869Z");
+        assert_eq!(millis.subsec_nanos(), 869_000_000);
+        assert_eq!(millis.unix_millis(), 1504089765869);
+        assert_eq!(millis.to_string(), "2017-08-30T10:42:45.869Z");
+        
+        let micros = ts("2017-08-30T10:42:45.000123+00:00");
+        assert_eq!(micros.subsec_nanos(), 123_000);
+        assert_eq!(micros.to_string(), "2017-08-30T10:42:45.000123+00:00");
+        
+        // same instant, written differently
+        assert_eq!(millis, ts("2017-08-30T10:42:45.869000+00:00"));
+    }
+    
+    #[test]
+    fn leap_days_and_centuries() {
+        assert_eq!(ts("2000-02-29T00:00:00Z").unix_secs(), 951782400);
+        assert_eq!(ts("2024-02-29T12:00:00Z").to_string(), "2024-02-29T12:00:00Z");
+        assert_eq!(ts("2000-03-01T00:00:00Z").unix_secs() - ts("2000-02-28T00:00:00Z").unix_secs(), 2 * 86400);
+        assert_eq!(ts("1900-03-01T00:00:00Z").unix_secs() - ts("1900-02-28T00:00:00Z").unix_secs(), 86400);
+        
+        assert!("1900-02-29T00:00:00Z".parse::<Timestamp>().is_err());
+        assert!("2023-02-29T00:00:00Z".parse::<Timestamp>().is_err());
+    }
+    
+    #[test]
+    fn before_epoch() {
+        let t = ts("1969-12-31T23:59:59.500Z");
+        assert_eq!(t.unix_secs(), -1);
+        assert_eq!(t.subsec_nanos(), 500_000_000);
+        assert_eq!(t.to_system_time(), UNIX_EPOCH - Duration::from_millis(500));
+        assert_eq!(t.to_string(), "1969-12-31T23:59:59.500Z");
+        
+        let old = ts("1900-01-01T00:00:00Z");
+        assert_eq!(old.unix_secs(), -2208988800);
+        assert_eq!(old.to_system_time(), UNIX_EPOCH - Duration::from_secs(2208988800));
+        
+        let from_time = Timestamp::from(UNIX_EPOCH - Duration::from_millis(1500));
+        assert_eq!(from_time.to_system_time(), UNIX_EPOCH - Duration::from_millis(1500));
+        assert_eq!(from_time.to_string(), "1969-12-31T23:59:58.500000+00:00");
+    }
+    
+    #[test]
+    fn discord_strings_round_trip() {
+        for s in [
+            "2017-08-30T10:42:45.869000+00:00",
+            "2023-10-13T14:02:11.734000+00:00",
+            "2021-04-05T17:07:08+00:00",
+            "2015-04-26T06:26:56.936000+00:00",
+            "2023-10-13T14:00:00Z",
+            "2023-10-13T14:00:00.000Z",
+        ] {
+            assert_eq!(ts(s).to_string(), s);
+            assert_eq!(serde_json::to_value(ts(s)).unwrap(), serde_json::Value::from(s));
+            
+            let bytes = bincode::serialize(&ts(s)).unwrap();
+            assert_eq!(bincode::deserialize::<Timestamp>(&bytes).unwrap().to_string(), s);
+        }
+    }
+    
+    #[test]
+    fn rejects_invalid() {
+        for s in [
+            "",
+            "2017",
+            "2017-08-30",
+            "2017-08-30T10:42:45",
+            "2017-08-30T10:42:45+00",
+            "2017-08-30T10:42:45+0000",
+            "2017-08-30T10:42:45.Z",
+            "2017-08-30T10:42:45.1234567890Z",
+            "2017-08-30T10:42:45Zjunk",
+            "2017-13-01T00:00:00Z",
+            "2017-00-01T00:00:00Z",
+            "2017-04-31T00:00:00Z",
+            "2017-08-30T24:00:00Z",
+            "2017-08-30T10:60:00Z",
+            "2017-08-30T10:42:60Z",
+            "2017-08-30T10:42:45+24:00",
+            "2017/08/30T10:42:45Z",
+            "2017-08-3xT10:42:45Z",
+            "+017-08-30T10:42:45Z",
+            "２０１７-08-30T10:42:45Z",
+            "2017-08-30T10:42:45.８Z",
+        ] {
+            assert!(s.parse::<Timestamp>().is_err(), "{:?} parsed", s);
+        }
+        assert!(serde_json::from_str::<Timestamp>("1504089765").is_err());
+    }
+}
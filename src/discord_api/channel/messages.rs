@@ -0,0 +1,436 @@
+#![allow(clippy::needless_lifetimes, clippy::redundant_field_names)]
+
+use std::cmp::Reverse;
+use std::collections::{HashSet, VecDeque};
+use std::time::{Duration, SystemTime};
+
+use futures::stream::{self, Stream};
+
+use reqwest::header::{ AUTHORIZATION, CONTENT_TYPE, CONTENT_LENGTH };
+use percent_encoding::{ utf8_percent_encode, NON_ALPHANUMERIC };
+
+use crate::discord::{
+    ChannelId,
+    MessageId,
+    Message,
+    Snowflake,
+};
+
+use crate::send_message::{Error, BulkDeleteError, EditMessage, TheClient};
+use crate::send_message::send_retry_rate_limit;
+use crate::ratelimit::Route;
+
+/// Which messages of a channel to get, relative to another message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessagesAt {
+    /// The newest messages.
+    Latest,
+    Before(MessageId),
+    After(MessageId),
+    Around(MessageId),
+}
+
+/// Which way `message_history` pages through a channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryDirection {
+    /// From newest to oldest.
+    Backward,
+    /// From oldest to newest.
+    Forward,
+}
+
+/// The most messages one request can get.
+pub const MAX_MESSAGES_PER_REQUEST: u8 = 100;
+
+const AUDIT_LOG_REASON: &str = "X-Audit-Log-Reason";
+
+/// Messages older than this can't be bulk deleted.
+pub const BULK_DELETE_MAX_AGE: Duration = Duration::from_secs(14 * 24 * 60 * 60);
+
+fn encode_reason(reason: &str) -> String {
+    utf8_percent_encode(reason, NON_ALPHANUMERIC).to_string()
+}
+
+pub async fn edit_message<'a>(
+    channel: ChannelId,
+    msg: MessageId,
+    edit: &EditMessage<'a>,
+    base_url: &str,
+    auth: &str,
+    client: &'a TheClient,
+    ) -> Result<Message, Error>
+{
+    edit.validate()?;
+    
+    let url = format!("{}/channels/{}/messages/{}",
+        base_url, channel, msg);
+    
+    let route = Route::new("PATCH /channels/{channel}/messages/{message}", channel);
+    
+    let body = send_retry_rate_limit(client, &route, || {
+        let body = serde_json::to_string(edit)
+            .map_err(|err| Error::Other(format!("Could not serialize message edit {}", err)))?;
+        
+        client.patch(&url)
+            .header(AUTHORIZATION, auth)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body)
+            .build()
+            .map_err(|err| err.into())
+    }).await?;
+    
+    let message: Message = serde_json::from_str(&body)
+        .map_err(|err| Error::Other(format!("Malformed edited message {}", err)))?;
+    
+    Ok(message)
+}
+
+pub async fn delete_message<'a>(
+    channel: ChannelId,
+    msg: MessageId,
+    reason: Option<&str>,
+    base_url: &str,
+    auth: &str,
+    client: &'a TheClient,
+    ) -> Result<(), Error>
+{
+    let url = format!("{}/channels/{}/messages/{}",
+        base_url, channel, msg);
+    
+    let route = Route::new("DELETE /channels/{channel}/messages/{message}", channel);
+    let reason = reason.map(encode_reason);
+    
+    let _body = send_retry_rate_limit(client, &route, || {
+        let mut req = client.delete(&url)
+            .header(AUTHORIZATION, auth)
+            .header(CONTENT_LENGTH, "0");
+        
+        if let Some(ref reason) = reason {
+            req = req.header(AUDIT_LOG_REASON, reason);
+        }
+        
+        req.build()
+            .map_err(|err| err.into())
+    }).await?;
+    
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct BulkDelete<'a> {
+    messages: &'a [MessageId],
+}
+
+// discord rejects the whole request if any of these don't hold
+fn check_bulk_delete(msgs: &[MessageId], now: SystemTime) -> Result<(), BulkDeleteError> {
+    if msgs.len() < 2 || msgs.len() > 100 {
+        return Err(BulkDeleteError::Count(msgs.len()));
+    }
+    
+    let mut seen = HashSet::with_capacity(msgs.len());
+    if let Some(msg) = msgs.iter().find(|msg| !seen.insert(**msg)) {
+        return Err(BulkDeleteError::Duplicate(*msg));
+    }
+    
+    let oldest = Snowflake::from_time(now - BULK_DELETE_MAX_AGE);
+    if let Some(msg) = msgs.iter().find(|msg| msg.0 < oldest) {
+        return Err(BulkDeleteError::TooOld(*msg));
+    }
+    
+    Ok(())
+}
+
+/// Deletes 2 to 100 distinct messages at once, none of them may be older
+/// than `BULK_DELETE_MAX_AGE`.
+pub async fn bulk_delete_messages<'a>(
+    channel: ChannelId,
+    msgs: &[MessageId],
+    reason: Option<&str>,
+    base_url: &str,
+    auth: &str,
+    client: &'a TheClient,
+    ) -> Result<(), Error>
+{
+    check_bulk_delete(msgs, SystemTime::now())?;
+    
+    let url = format!("{}/channels/{}/messages/bulk-delete",
+        base_url, channel);
+    
+    let route = Route::new("POST /channels/{channel}/messages/bulk-delete", channel);
+    let reason = reason.map(encode_reason);
+    
+    let body = serde_json::to_string(&BulkDelete { messages: msgs })
+        .map_err(|err| Error::Other(format!("Could not serialize bulk delete {}", err)))?;
+    
+    let _body = send_retry_rate_limit(client, &route, || {
+        let mut req = client.post(&url)
+            .header(AUTHORIZATION, auth)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body.clone());
+        
+        if let Some(ref reason) = reason {
+            req = req.header(AUDIT_LOG_REASON, reason);
+        }
+        
+        req.build()
+            .map_err(|err| err.into())
+    }).await?;
+    
+    Ok(())
+}
+
+/// Publishes a message in an announcement channel to the channels following it.
+pub async fn crosspost_message<'a>(
+    channel: ChannelId,
+    msg: MessageId,
+    base_url: &str,
+    auth: &str,
+    client: &'a TheClient,
+    ) -> Result<Message, Error>
+{
+    let url = format!("{}/channels/{}/messages/{}/crosspost",
+        base_url, channel, msg);
+    
+    let route = Route::new("POST /channels/{channel}/messages/{message}/crosspost", channel);
+    
+    let body = send_retry_rate_limit(client, &route, || {
+        client.post(&url)
+            .header(AUTHORIZATION, auth)
+            .header(CONTENT_LENGTH, "0")
+            .build()
+            .map_err(|err| err.into())
+    }).await?;
+    
+    let message: Message = serde_json::from_str(&body)
+        .map_err(|err| Error::Other(format!("Malformed crossposted message {}", err)))?;
+    
+    Ok(message)
+}
+
+/// Gets up to `limit` messages, newest first. `limit` is clamped to 1 to
+/// `MAX_MESSAGES_PER_REQUEST`.
+pub async fn get_messages<'a>(
+    channel: ChannelId,
+    at: MessagesAt,
+    limit: u8,
+    base_url: &str,
+    auth: &str,
+    client: &'a TheClient,
+    ) -> Result<Vec<Message>, Error>
+{
+    let limit = limit.clamp(1, MAX_MESSAGES_PER_REQUEST);
+    let anchor = match at {
+        MessagesAt::Latest => String::new(),
+        MessagesAt::Before(msg) => format!("&before={}", msg),
+        MessagesAt::After(msg) => format!("&after={}", msg),
+        MessagesAt::Around(msg) => format!("&around={}", msg),
+    };
+    
+    let url = format!("{}/channels/{}/messages?limit={}{}",
+        base_url, channel, limit, anchor);
+    
+    let route = Route::new("GET /channels/{channel}/messages", channel);
+    
+    let body = send_retry_rate_limit(client, &route, || {
+        client.get(&url)
+            .header(AUTHORIZATION, auth)
+            .header(CONTENT_TYPE, "application/json")
+            .header(CONTENT_LENGTH, "0")
+            .build()
+            .map_err(|err| err.into())
+    }).await?;
+    
+    let messages: Vec<Message> = serde_json::from_str(&body)
+        .map_err(|err| Error::Other(format!("Malformed get messages {}", err)))?;
+    
+    Ok(messages)
+}
+
+// where a history stream is, kept apart from the requests
+struct Pages {
+    direction: HistoryDirection,
+    // the last message yielded, `None` before the first page
+    cursor: Option<MessageId>,
+    page: VecDeque<Message>,
+    done: bool,
+}
+
+impl Pages {
+    fn new(direction: HistoryDirection) -> Self {
+        Pages {
+            direction: direction,
+            cursor: None,
+            page: VecDeque::new(),
+            done: false,
+        }
+    }
+    
+    /// Whether the next message has to wait for another page.
+    fn needs_page(&self) -> bool {
+        self.page.is_empty() && !self.done
+    }
+    
+    /// Where the next page starts.
+    fn next_at(&self) -> MessagesAt {
+        match (self.direction, self.cursor) {
+            (HistoryDirection::Backward, None) => MessagesAt::Latest,
+            (HistoryDirection::Backward, Some(msg)) => MessagesAt::Before(msg),
+            // every message is after id 0
+            (HistoryDirection::Forward, None) => MessagesAt::After(MessageId(Snowflake(0))),
+            (HistoryDirection::Forward, Some(msg)) => MessagesAt::After(msg),
+        }
+    }
+    
+    /// Adds a page as discord returned it, newest first whichever the
+    /// direction. A short page is the last one.
+    fn add_page(&mut self, mut messages: Vec<Message>) {
+        self.done = messages.len() < MAX_MESSAGES_PER_REQUEST as usize;
+        
+        match self.direction {
+            HistoryDirection::Backward => messages.sort_by_key(|msg| Reverse(msg.id)),
+            HistoryDirection::Forward => messages.sort_by_key(|msg| msg.id),
+        }
+        self.page.extend(messages);
+    }
+    
+    fn pop(&mut self) -> Option<Message> {
+        let message = self.page.pop_front()?;
+        self.cursor = Some(message.id);
+        Some(message)
+    }
+}
+
+struct History {
+    channel: ChannelId,
+    pages: Pages,
+    base_url: String,
+    auth: String,
+    client: TheClient,
+}
+
+impl History {
+    async fn next_page(&mut self) -> Result<(), Error> {
+        let messages = get_messages(self.channel, self.pages.next_at(), MAX_MESSAGES_PER_REQUEST,
+            &self.base_url, &self.auth, &self.client).await?;
+        
+        self.pages.add_page(messages);
+        
+        Ok(())
+    }
+}
+
+/// Every message in `channel`, one page of `MAX_MESSAGES_PER_REQUEST` at a
+/// time. Pages are requested as the stream is polled so a slow consumer
+/// doesn't use up the rate limit. The stream ends after the first error.
+pub fn message_history(
+    channel: ChannelId,
+    direction: HistoryDirection,
+    base_url: String,
+    auth: String,
+    client: TheClient,
+    ) -> impl Stream<Item = Result<Message, Error>>
+{
+    let history = History {
+        channel: channel,
+        pages: Pages::new(direction),
+        base_url: base_url,
+        auth: auth,
+        client: client,
+    };
+    
+    stream::unfold(Some(history), |history| async move {
+        let mut history = history?;
+        
+        if history.pages.needs_page() {
+            if let Err(err) = history.next_page().await {
+                return Some((Err(err), None))
+            }
+        }
+        
+        let message = history.pages.pop()?;
+        Some((Ok(message), Some(history)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    use crate::discord::fixtures;
+    
+    fn at(now: SystemTime, age: Duration) -> MessageId {
+        MessageId(Snowflake::from_time(now - age))
+    }
+    
+    #[test]
+    fn bulk_delete_checks() {
+        let now = SystemTime::now();
+        let a = at(now, Duration::from_secs(60));
+        let b = at(now, Duration::from_secs(120));
+        
+        let old = at(now, BULK_DELETE_MAX_AGE + Duration::from_secs(1));
+        
+        assert_eq!(check_bulk_delete(&[a, b], now), Ok(()));
+        assert_eq!(check_bulk_delete(&[a], now), Err(BulkDeleteError::Count(1)));
+        assert_eq!(check_bulk_delete(&vec![a; 101], now), Err(BulkDeleteError::Count(101)));
+        assert_eq!(check_bulk_delete(&[a, b, a], now), Err(BulkDeleteError::Duplicate(a)));
+        assert_eq!(check_bulk_delete(&[a, old], now), Err(BulkDeleteError::TooOld(old)));
+    }
+    
+    fn message(id: u64) -> Message {
+        fixtures::message(serde_json::json!({"id": id.to_string()}))
+    }
+    
+    // a page as discord returns it, newest first
+    fn page<I: DoubleEndedIterator<Item = u64>>(ids: I) -> Vec<Message> {
+        ids.rev().map(message).collect()
+    }
+    
+    fn pop_all(pages: &mut Pages) -> Vec<u64> {
+        std::iter::from_fn(|| pages.pop()).map(|msg| (msg.id.0).0).collect()
+    }
+    
+    fn id(id: u64) -> MessageId {
+        MessageId(Snowflake(id))
+    }
+    
+    #[test]
+    fn backward_history() {
+        let mut pages = Pages::new(HistoryDirection::Backward);
+        assert!(pages.needs_page());
+        assert_eq!(pages.next_at(), MessagesAt::Latest);
+        
+        pages.add_page(page(101..=200));
+        assert_eq!(pop_all(&mut pages), (101..=200).rev().collect::<Vec<_>>());
+        assert!(pages.needs_page());
+        assert_eq!(pages.next_at(), MessagesAt::Before(id(101)));
+        
+        pages.add_page(vec![message(99), message(100), message(98)]);
+        assert_eq!(pop_all(&mut pages), [100, 99, 98]);
+        assert!(!pages.needs_page());
+    }
+    
+    #[test]
+    fn forward_history() {
+        let mut pages = Pages::new(HistoryDirection::Forward);
+        assert!(pages.needs_page());
+        assert_eq!(pages.next_at(), MessagesAt::After(id(0)));
+        
+        pages.add_page(page(1..=100));
+        assert_eq!(pop_all(&mut pages), (1..=100).collect::<Vec<_>>());
+        assert!(pages.needs_page());
+        assert_eq!(pages.next_at(), MessagesAt::After(id(100)));
+        
+        pages.add_page(page(101..=102));
+        assert_eq!(pop_all(&mut pages), [101, 102]);
+        assert!(!pages.needs_page());
+    }
+    
+    #[test]
+    fn empty_page_ends_history() {
+        let mut pages = Pages::new(HistoryDirection::Forward);
+        pages.add_page(Vec::new());
+        assert!(!pages.needs_page());
+        assert!(pages.pop().is_none());
+    }
+}
@@ -1,42 +1,45 @@
+#![allow(clippy::needless_lifetimes)]
 
 // use hyper::{ Client, Request, Body };
 use reqwest::header::{ AUTHORIZATION, CONTENT_TYPE, CONTENT_LENGTH };
 
 use crate::discord::{
-    Snowflake,
+    GuildId,
+    UserId,
     GuildMember,
 };
 
 use crate::send_message::Error;
-use crate::send_message::send_retry_rate_limit;
+use crate::send_message::{send_retry_rate_limit, TheClient};
+use crate::ratelimit::Route;
 
 pub mod roles;
 
 const LIMIT: usize = 1000;
 
 async fn get_members_inner<'a>(
-    server: Snowflake,
-    after: Option<Snowflake>,
+    server: GuildId,
+    after: Option<UserId>,
     // msg: Snowflake,
     // emoji: &str,
     base_url: &str,
     auth: &str,
     // client: &'a Client<C, Body>,
-    client: &'a reqwest::Client,
+    client: &'a TheClient,
     ) -> Result<Vec<GuildMember>, Error>
     // where
     //     C: hyper::client::connect::Connect + 'static + Clone + Send + Sync,
 {
-    let Snowflake(server_id) = server;
-    
     let mut url = format!("{}/guilds/{}/members?limit={}",
-        base_url, server_id, LIMIT);
+        base_url, server, LIMIT);
     
-    if let Some(Snowflake(after)) = after {
+    if let Some(after) = after {
         url.push_str(&format!("&after={}", after))
     }
     
-    let body = send_retry_rate_limit(client, || {
+    let route = Route::new("GET /guilds/{guild}/members", server);
+    
+    let body = send_retry_rate_limit(client, &route, || {
         // Request::builder()
         client.get(&url)
             // .method("GET")
@@ -57,13 +60,13 @@ async fn get_members_inner<'a>(
 }
 
 pub async fn get_members<'a>(
-    server: Snowflake,
+    server: GuildId,
     // msg: Snowflake,
     // emoji: &str,
     base_url: &str,
     auth: &str,
     // client: &'a Client<C, Body>,
-    client: &'a reqwest::Client,
+    client: &'a TheClient,
     ) -> Result<Vec<GuildMember>, Error>
     // where
     //     C: hyper::client::connect::Connect + 'static + Clone + Send + Sync,
@@ -72,12 +75,12 @@ pub async fn get_members<'a>(
     let mut last = None;
     
     loop {
-        let data = get_members_inner(server.clone(), last.clone(), base_url, auth, client).await?;
+        let data = get_members_inner(server, last, base_url, auth, client).await?;
         
         let is_end = data.len() < LIMIT;
         
-        if let Some(max) = data.iter().filter_map(|x| x.user.as_ref()).map(|x| x.id.0).max() {
-            last = Some(Snowflake(max))
+        if let Some(max) = data.iter().filter_map(|x| x.user.as_ref()).map(|x| x.id).max() {
+            last = Some(max)
         }
         
         for user in data {
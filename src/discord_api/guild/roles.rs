@@ -1,31 +1,35 @@
+#![allow(clippy::needless_lifetimes)]
 
 // use hyper::{ Client, Request, Body };
 use reqwest::header::{ AUTHORIZATION, CONTENT_TYPE, CONTENT_LENGTH };
 
-use crate::discord::Snowflake;
+use crate::discord::{GuildId, UserId, RoleId};
 
 use crate::send_message::Error;
-use crate::send_message::send_retry_rate_limit;
+use crate::send_message::{send_retry_rate_limit, TheClient};
+use crate::ratelimit::Route;
 
 pub async fn add_member_role<'a>(
-    guild_id: Snowflake,
-    user_id: Snowflake,
-    role_id: Snowflake,
+    guild_id: GuildId,
+    user_id: UserId,
+    role_id: RoleId,
     // after: Option<Snowflake>,
     // msg: Snowflake,
     // emoji: &str,
     base_url: &str,
     auth: &str,
     // client: &'a Client<C, Body>,
-    client: &'a reqwest::Client,
+    client: &'a TheClient,
     ) -> Result<(), Error>
     // where
     //     C: hyper::client::connect::Connect + 'static + Clone + Send + Sync,
 {
     let url = format!("{}/guilds/{}/members/{}/roles/{}",
-        base_url, guild_id.0, user_id.0, role_id.0);
+        base_url, guild_id, user_id, role_id);
     
-    let _body = send_retry_rate_limit(client, || {
+    let route = Route::new("PUT /guilds/{guild}/members/{user}/roles/{role}", guild_id);
+    
+    let _body = send_retry_rate_limit(client, &route, || {
         // Request::builder()
         client.put(&url)
             // .method("PUT")
@@ -42,24 +46,26 @@ pub async fn add_member_role<'a>(
 }
 
 pub async fn remove_member_role<'a>(
-    guild_id: Snowflake,
-    user_id: Snowflake,
-    role_id: Snowflake,
+    guild_id: GuildId,
+    user_id: UserId,
+    role_id: RoleId,
     // after: Option<Snowflake>,
     // msg: Snowflake,
     // emoji: &str,
     base_url: &str,
     auth: &str,
     // client: &'a Client<C, Body>,
-    client: &'a reqwest::Client,
+    client: &'a TheClient,
     ) -> Result<(), Error>
     // where
     //     C: hyper::client::connect::Connect + 'static + Clone + Send + Sync,
 {
     let url = format!("{}/guilds/{}/members/{}/roles/{}",
-        base_url, guild_id.0, user_id.0, role_id.0);
+        base_url, guild_id, user_id, role_id);
+    
+    let route = Route::new("DELETE /guilds/{guild}/members/{user}/roles/{role}", guild_id);
     
-    let _body = send_retry_rate_limit(client, || {
+    let _body = send_retry_rate_limit(client, &route, || {
         // Request::builder()
         // client.request()
         client.delete(&url)
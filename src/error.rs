@@ -0,0 +1,63 @@
+
+use std::fmt;
+use std::io;
+
+use crate::discord::{ApiError, ErrorCode};
+use crate::gateway::GatewayError;
+pub use crate::send_message::Error as RestError;
+
+/// The error returned by `Discord`, `SendHandle` and the other top level
+/// functions, the underlying error is its `source`.
+#[derive(Debug)]
+pub enum Error {
+    Rest(RestError),
+    Gateway(GatewayError),
+    /// The ratelimit service run by `ratelimit::serve_unix` stopped.
+    RateLimitService(io::Error),
+}
+
+impl Error {
+    /// The error discord returned, if a REST request got a JSON error response.
+    pub fn api_error(&self) -> Option<&ApiError> {
+        match self {
+            Error::Rest(err) => err.api_error(),
+            Error::Gateway(_) | Error::RateLimitService(_) => None,
+        }
+    }
+    
+    pub fn code(&self) -> Option<ErrorCode> {
+        self.api_error().map(|err| err.code)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Rest(_) => write!(f, "discord REST request failed"),
+            Error::Gateway(_) => write!(f, "discord gateway error"),
+            Error::RateLimitService(_) => write!(f, "ratelimit service failed"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Rest(err) => Some(err),
+            Error::Gateway(err) => Some(err),
+            Error::RateLimitService(err) => Some(err),
+        }
+    }
+}
+
+impl From<RestError> for Error {
+    fn from(err: RestError) -> Self {
+        Error::Rest(err)
+    }
+}
+
+impl From<GatewayError> for Error {
+    fn from(err: GatewayError) -> Self {
+        Error::Gateway(err)
+    }
+}
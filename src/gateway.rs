@@ -1,3 +1,6 @@
+#![allow(clippy::manual_async_fn, clippy::needless_lifetimes, clippy::redundant_field_names)]
+
+use std::fmt;
 
 use reqwest::header::AUTHORIZATION;
 
@@ -28,20 +31,37 @@ impl GatewayInfo {
     }
 }
 
-#[derive(Debug, Fail)]
+#[derive(Debug)]
 pub enum GatewayError {
-    #[fail(display = "Gateway Misc: {}", 0)]
     Misc(String),
-    #[fail(display = "Web Socket Error: {}", 0)]
     WebSocket(WebSocketError),
-    #[fail(display = "Malformed Payload Error")]
     MalformedPayload,
-    #[fail(display = "Request Error {}", 0)]
     ReqwestError(reqwest::Error),
-    #[fail(display = "Invalid Session")]
     InvalidSession,
 }
 
+impl fmt::Display for GatewayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GatewayError::Misc(reason) => write!(f, "gateway error: {}", reason),
+            GatewayError::WebSocket(_) => write!(f, "gateway websocket failed"),
+            GatewayError::MalformedPayload => write!(f, "malformed gateway payload"),
+            GatewayError::ReqwestError(_) => write!(f, "gateway request failed"),
+            GatewayError::InvalidSession => write!(f, "invalid session"),
+        }
+    }
+}
+
+impl std::error::Error for GatewayError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GatewayError::WebSocket(err) => Some(err),
+            GatewayError::ReqwestError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
 impl From<WebSocketError> for GatewayError {
     fn from(err: WebSocketError) -> Self {
         GatewayError::WebSocket(err)
@@ -52,6 +72,7 @@ impl From<ParsePayloadError> for GatewayError {
     fn from(err: ParsePayloadError) -> Self {
         match err {
             ParsePayloadError::Malformed => GatewayError::MalformedPayload,
+            ParsePayloadError::MalformedEvent { .. } => GatewayError::MalformedPayload,
             _ => GatewayError::Misc(format!("Unexpected Parse Payload Error: {:?}", err))
         }
     }
@@ -85,7 +106,7 @@ async fn get_gateway<'a>(token: &'a str, base_url: &'a str, client: &'a TheClien
     
     let info: GatewayInfo = serde_json::from_slice(b)
         .map_err(|err| {
-            eprintln!("deserialize error (get_gateway): {}", err); GatewayError::Misc("deserialize".into());
+            eprintln!("deserialize error (get_gateway): {}", err);
             let s = String::from_utf8_lossy(b);
             eprintln!("response (get_gateway) {}", s);
             GatewayError::Misc("deserialize".into())
@@ -98,12 +119,13 @@ use self::gateway_ws::{WebSocket, WebSocketBuilder, Message};
 use self::gateway_ws::SenderM;
 use serde_json::value::Value;
 use serde_json::from_value;
+use serde::de::DeserializeOwned;
 use crate::discord;
 
 #[derive(Debug)]
 pub enum Event {
     MessageCreate(discord::Message),
-    MessageUpdate(discord::Message),
+    MessageUpdate(discord::MessageUpdate),
     MessageDelete(discord::DeletedMessage),
     MessageReactionAdd(MessageReactionAdd),
     MessageReactionRemove(MessageReactionRemove),
@@ -118,10 +140,17 @@ pub enum Event {
     ChannelDelete(discord::Channel),
     Ready(Ready),
     Resumed,
-    VoiceStateUpdate(VoiceStateUpdate),
+    VoiceStateUpdate(discord::VoiceState),
     VoiceServerUpate(VoiceServerUpdate),
-    PresenceUpdate(PresenceUpdate),
+    PresenceUpdate(discord::Presence),
     Unknown(String, Value),
+    /// A known dispatch whose data could not be decoded, only delivered when
+    /// the gateway was built with `unparsed_events(true)`.
+    Unparsed {
+        name: String,
+        raw: Value,
+        error: serde_json::Error,
+    },
 }
 
 #[allow(dead_code)]
@@ -132,44 +161,15 @@ pub struct Hello {
     trace: Vec<String>,
 }
 
-// BBBBBB {"t":"PRESENCE_UPDATE",
-// "s":107210,
-// "op":0,
-// "d":{
-//     "user":{"id":"-"},
-//     "status":"online",
-//     "roles":["-"],
-//     "guild_id":"-",
-//     "game":{
-//         "type":4,"name":"Custom Status","id":"custom",
-//         "emoji":{"name":"-","id":"-","animated":true
-//        },
-//        "created_at":-
-//      },
-//     "client_status":{"desktop":"online"},
-//     "activities":[
-        // {"type":4,"name":"Custom Status","id":"custom","emoji":{"name":"-","id":"-","animated":true},
-//      "created_at":-}]}}
-
 use crate::discord::{
-    Snowflake,
+    GuildId,
+    ChannelId,
+    UserId,
+    MessageId,
 };
 
-#[allow(dead_code)]
-#[derive(Debug, Deserialize)]
-pub struct IdUser {
-    id: Snowflake,
-}
-
-#[allow(dead_code)]
-#[derive(Debug, Deserialize)]
-pub struct PresenceUpdate {
-    user: IdUser,
-    status: String,
-    roles: Vec<Snowflake>,
-    guild_id: Snowflake,
-}
-
+// events are most of what comes through, boxing them would only add an allocation
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
 pub enum GatewayMessage {
     // MessageCreate(Value),
@@ -194,59 +194,44 @@ struct Payload {
 }
 
 #[allow(dead_code)]
-#[derive(Debug, Deserialize)]
-pub struct Emoji {
-    id: Option<Snowflake>,
-    pub name: String,
-}
-
-#[allow(dead_code)]
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct MessageReactionAdd {
-    pub user_id: Snowflake,
-    pub channel_id: Snowflake,
-    pub message_id: Snowflake,
-    guild_id: Option<Snowflake>,
+    pub user_id: UserId,
+    pub channel_id: ChannelId,
+    pub message_id: MessageId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    guild_id: Option<GuildId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     member: Option<discord::GuildMember>,
-    pub emoji: Emoji,
+    pub emoji: discord::PartialEmoji,
 }
 
 #[allow(dead_code)]
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct MessageReactionRemove {
-    pub user_id: Snowflake,
-    pub channel_id: Snowflake,
-    pub message_id: Snowflake,
-    guild_id: Option<Snowflake>,
-    pub emoji: Emoji,
+    pub user_id: UserId,
+    pub channel_id: ChannelId,
+    pub message_id: MessageId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    guild_id: Option<GuildId>,
+    pub emoji: discord::PartialEmoji,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct UnavailableGuild {
-    pub id: Snowflake,
-}
-
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Ready {
     pub user: discord::User,
     pub session_id: String,
     #[serde(rename = "v")]
     pub protocol_version: usize,
-    pub guilds: Vec<UnavailableGuild>,
+    pub guilds: Vec<discord::UnavailableGuild>,
 }
 
 #[allow(dead_code)]
-#[derive(Debug, Deserialize)]
-pub struct VoiceStateUpdate {
-    session_id: String,
-}
-
-#[allow(dead_code)]
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct VoiceServerUpdate {
     endpoint: String,
     token: String,
-    guild_id: String,
+    guild_id: GuildId,
 }
 
 #[derive(Debug, Clone)]
@@ -263,6 +248,7 @@ pub struct Gateway {
     resume: Option<ResumeInfo>,
     pub(crate) seq_num: Option<u64>,
     pub(crate) did_resume: Option<bool>,
+    unparsed_events: bool,
     // pub spawner: S,
 }
 
@@ -271,189 +257,87 @@ use self::gateway_ws::jank_spawn;
 #[derive(Debug)]
 enum ParsePayloadError {
     Unkown,
-    Malformed
+    Malformed,
+    MalformedEvent {
+        name: String,
+        raw: Value,
+        error: serde_json::Error,
+    },
+}
+
+fn parse_event<T: DeserializeOwned>(data: &Value) -> Result<T, serde_json::Error> {
+    T::deserialize(data)
+}
+
+/// Decodes the data of an op 0 dispatch, handing the raw data back alongside
+/// the error if it does not match the event's model.
+fn parse_dispatch(name: &str, data: Value) -> Result<Event, (serde_json::Error, Value)> {
+    let event = match name {
+        "MESSAGE_CREATE" => parse_event(&data).map(Event::MessageCreate),
+        "MESSAGE_UPDATE" => parse_event(&data).map(Event::MessageUpdate),
+        "MESSAGE_DELETE" => parse_event(&data).map(Event::MessageDelete),
+        "GUILD_CREATE" => parse_event(&data).map(Event::GuildCreate),
+        "GUILD_UPDATE" => parse_event(&data).map(Event::GuildUpdate),
+        "GUILD_DELETE" => parse_event(&data).map(Event::GuildDelete),
+        "GUILD_ROLE_CREATE" => parse_event(&data).map(Event::GuildRoleCreate),
+        "GUILD_ROLE_UPDATE" => parse_event(&data).map(Event::GuildRoleUpdate),
+        "GUILD_ROLE_DELETE" => parse_event(&data).map(Event::GuildRoleDelete),
+        "CHANNEL_CREATE" => parse_event(&data).map(Event::ChannelCreate),
+        "CHANNEL_UPDATE" => parse_event(&data).map(Event::ChannelUpdate),
+        "CHANNEL_DELETE" => parse_event(&data).map(Event::ChannelDelete),
+        "READY" => parse_event(&data).map(Event::Ready),
+        "RESUMED" => Ok(Event::Resumed),
+        "VOICE_STATE_UPDATE" => {
+            // {
+            //     "t":"VOICE_STATE_UPDATE",
+            //     "s":4,"op":0,
+            //     "d":{
+            //         "member":{
+            //             "user":{"username":"ndirc","id":"-","discriminator":"-","bot":true,"avatar":null},
+            //             "roles":[],"mute":false,"joined_at":"2017-08-30T10:42:45.869000+00:00",
+            //             "hoisted_role":null,"deaf":false
+            //         },
+            //         "user_id":"-","suppress":false,
+            //         "session_id":"-",
+            //         "self_video":false,"self_mute":false,"self_deaf":false,"mute":false,
+            //         "guild_id":"-","deaf":false,"channel_id":"-"
+            //     }
+            // }
+            parse_event(&data).map(Event::VoiceStateUpdate)
+        }
+        "VOICE_SERVER_UPDATE" => {
+            // {
+            //     "t":"VOICE_SERVER_UPDATE","s":5,"op":0,
+            //     "d":{
+            //         "token":"-",
+            //         "guild_id":"-",
+            //         "endpoint":"-"
+            //     }
+            // }
+            parse_event(&data).map(Event::VoiceServerUpate)
+        }
+        "PRESENCE_UPDATE" => parse_event(&data).map(Event::PresenceUpdate),
+        "MESSAGE_REACTION_ADD" => parse_event(&data).map(Event::MessageReactionAdd),
+        "MESSAGE_REACTION_REMOVE" => parse_event(&data).map(Event::MessageReactionRemove),
+        _ => return Ok(Event::Unknown(name.into(), data)),
+    };
+    
+    event.map_err(|err| (err, data))
 }
 
 fn parse_payload_inner(payload: Payload) -> Result<Option<GatewayMessage>, ParsePayloadError> {
     let out_msg: GatewayMessage = match payload.op {
         0 => {
-            match payload.event.as_ref().map(|s| s.as_str()) {
-                Some("MESSAGE_CREATE") => {
-                    let message: discord::Message = from_value(payload.data).map_err(|err| {
-                        eprintln!("parsing event failed for op 0 MESSAGE_CREATE: {:?}", err);
-                        ParsePayloadError::Malformed
-                    })?;
-                    GatewayMessage::Event(Event::MessageCreate(message))
-                }
-                Some("MESSAGE_UPDATE") => {
-                    let message: discord::Message = from_value(payload.data).map_err(|err| {
-                        eprintln!("parsing event failed for op 0 MESSAGE_UPDATE: {:?}", err);
-                        ParsePayloadError::Malformed
-                    })?;
-                    GatewayMessage::Event(Event::MessageUpdate(message))
-                }
-                Some("MESSAGE_DELETE") => {
-                    let message: discord::DeletedMessage = from_value(payload.data).map_err(|err| {
-                        eprintln!("parsing event failed for op 0 MESSAGE_UPDATE: {:?}", err);
-                        ParsePayloadError::Malformed
-                    })?;
-                    GatewayMessage::Event(Event::MessageDelete(message))
-                }
-                Some("GUILD_CREATE") => {
-                    let guild: discord::Guild = from_value(payload.data).map_err(|err| {
-                        eprintln!("parsing event failed for op 0 GUILD_CREATE: {:?}", err);
-                        ParsePayloadError::Malformed
-                    })?;
-                    
-                    GatewayMessage::Event(Event::GuildCreate(guild))
-                }
-                Some("GUILD_UPDATE") => {
-                    let guild: discord::Guild = from_value(payload.data).map_err(|err| {
-                        eprintln!("parsing event failed for op 0 GUILD_UPDATE: {:?}", err);
-                        ParsePayloadError::Malformed
-                    })?;
-                    
-                    GatewayMessage::Event(Event::GuildUpdate(guild))
-                }
-                Some("GUILD_DELETE") => {
-                    let guild: discord::UnavailableGuild = from_value(payload.data).map_err(|err| {
-                        eprintln!("parsing event failed for op 0 GUILD_DELETE: {:?}", err);
-                        ParsePayloadError::Malformed
-                    })?;
-                    
-                    GatewayMessage::Event(Event::GuildDelete(guild))
-                }
-                Some("GUILD_ROLE_CREATE") => {
-                    let data: discord::CreatedRole = from_value(payload.data).map_err(|err| {
-                        eprintln!("parsing event failed for op 0 GUILD_ROLE_CREATE: {:?}", err);
-                        ParsePayloadError::Malformed
-                    })?;
-                    
-                    GatewayMessage::Event(Event::GuildRoleCreate(data))
-                }
-                Some("GUILD_ROLE_UPDATE") => {
-                    let data: discord::CreatedRole = from_value(payload.data).map_err(|err| {
-                        eprintln!("parsing event failed for op 0 GUILD_ROLE_UPDATE: {:?}", err);
-                        ParsePayloadError::Malformed
-                    })?;
-                    
-                    GatewayMessage::Event(Event::GuildRoleUpdate(data))
-                }
-                Some("GUILD_ROLE_DELETE") => {
-                    let data: discord::DeletedRole = from_value(payload.data).map_err(|err| {
-                        eprintln!("parsing event failed for op 0 GUILD_ROLE_DELETE: {:?}", err);
-                        ParsePayloadError::Malformed
-                    })?;
-                    
-                    GatewayMessage::Event(Event::GuildRoleDelete(data))
-                }
-                Some("CHANNEL_CREATE") => {
-                    let data: discord::Channel = from_value(payload.data).map_err(|err| {
-                        eprintln!("parsing event failed for op 0 CHANNEL_CREATE: {:?}", err);
-                        ParsePayloadError::Malformed
-                    })?;
-                    
-                    GatewayMessage::Event(Event::ChannelCreate(data))
-                }
-                Some("CHANNEL_UPDATE") => {
-                    let data: discord::Channel = from_value(payload.data).map_err(|err| {
-                        eprintln!("parsing event failed for op 0 CHANNEL_UPDATE: {:?}", err);
-                        ParsePayloadError::Malformed
-                    })?;
-                    
-                    GatewayMessage::Event(Event::ChannelUpdate(data))
-                }
-                Some("CHANNEL_DELETE") => {
-                    let data: discord::Channel = from_value(payload.data).map_err(|err| {
-                        eprintln!("parsing event failed for op 0 CHANNEL_DELETE: {:?}", err);
-                        ParsePayloadError::Malformed
-                    })?;
-                    
-                    GatewayMessage::Event(Event::ChannelDelete(data))
-                }
-                Some("READY") => {
-                    let ready: Ready = from_value(payload.data).map_err(|err| {
-                        eprintln!("parsing event failed for op 0 READY: {:?}", err);
-                        ParsePayloadError::Malformed
-                    })?;
-                    GatewayMessage::Event(Event::Ready(ready))
-                }
-                Some("RESUMED") => {
-                    GatewayMessage::Event(Event::Resumed)
-                }
-                Some("VOICE_STATE_UPDATE") => {
-                    // {
-                    //     "t":"VOICE_STATE_UPDATE",
-                    //     "s":4,"op":0,
-                    //     "d":{
-                    //         "member":{
-                    //             "user":{"username":"ndirc","id":"-","discriminator":"-","bot":true,"avatar":null},
-                    //             "roles":[],"mute":false,"joined_at":"2017-08-30T10:42:45.869000+00:00",
-                    //             "hoisted_role":null,"deaf":false
-                    //         },
-                    //         "user_id":"-","suppress":false,
-                    //         "session_id":"-",
-                    //         "self_video":false,"self_mute":false,"self_deaf":false,"mute":false,
-                    //         "guild_id":"-","deaf":false,"channel_id":"-"
-                    //     }
-                    // }
-                    
-                    let session_id = payload.data
-                        .get("session_id").expect("VOICE_STATE_UPDATE session id missing")
-                        .as_str().expect("VOICE_STATE_UPDATE session id not string");
-                    
-                    let obj = VoiceStateUpdate {
-                        session_id: session_id.into(),
-                    };
-                    
-                    GatewayMessage::Event(Event::VoiceStateUpdate(obj))
-                }
-                Some("VOICE_SERVER_UPDATE") => {
-                    // {
-                    //     "t":"VOICE_SERVER_UPDATE","s":5,"op":0,
-                    //     "d":{
-                    //         "token":"-",
-                    //         "guild_id":"-",
-                    //         "endpoint":"-"
-                    //     }
-                    // }
-                    
-                    let update: VoiceServerUpdate = from_value(payload.data).map_err(|err| {
-                        eprintln!("parsing voice server update failed for op 0 VOICE_SERVER_UPDATE: {:?}", err);
-                        ParsePayloadError::Malformed
-                    })?;
-                    
-                    GatewayMessage::Event(Event::VoiceServerUpate(update))
-                }
-                Some("PRESENCE_UPDATE") => {
-                    let update: PresenceUpdate = from_value(payload.data).map_err(|err| {
-                        eprintln!("parsing presence update failed PRESENCE_UPDATE: {:?}", err);
-                        ParsePayloadError::Malformed
-                    })?;
-                    
-                    GatewayMessage::Event(Event::PresenceUpdate(update))
-                }
-                Some("MESSAGE_REACTION_ADD") => {
-                    let data: MessageReactionAdd = from_value(payload.data).map_err(|err| {
-                        eprintln!("parsing failed MESSAGE_REACTION_ADD: {:?}", err);
-                        ParsePayloadError::Malformed
-                    })?;
-                    
-                    GatewayMessage::Event(Event::MessageReactionAdd(data))
-                }
-                Some("MESSAGE_REACTION_REMOVE") => {
-                    let data: MessageReactionRemove = from_value(payload.data).map_err(|err| {
-                        eprintln!("parsing failed MESSAGE_REACTION_REMOVE: {:?}", err);
-                        ParsePayloadError::Malformed
-                    })?;
-                    
-                    GatewayMessage::Event(Event::MessageReactionRemove(data))
-                }
-                Some(event_type) => {
-                    GatewayMessage::Event(Event::Unknown(event_type.into(), payload.data))
-                }
-                _ => {
-                    return Err(ParsePayloadError::Unkown)
+            let name = match payload.event {
+                Some(name) => name,
+                None => return Err(ParsePayloadError::Unkown),
+            };
+            
+            match parse_dispatch(&name, payload.data) {
+                Ok(event) => GatewayMessage::Event(event),
+                Err((error, raw)) => {
+                    eprintln!("parsing event failed for op 0 {}: {:?}", name, error);
+                    return Err(ParsePayloadError::MalformedEvent { name, raw, error })
                 }
             }
         }
@@ -480,16 +364,24 @@ fn parse_payload_inner(payload: Payload) -> Result<Option<GatewayMessage>, Parse
         }
     };
     
-    return Ok(Some(out_msg))
+    Ok(Some(out_msg))
 }
 
-fn parse_payload(payload: Payload) -> (Result<Option<GatewayMessage>, ParsePayloadError>, Option<u64>) {
+/// A dispatch that fails to decode becomes `Event::Unparsed` with
+/// `unparsed_events` and is skipped without, its seq is returned either way.
+fn parse_payload(payload: Payload, unparsed_events: bool) -> (Result<Option<GatewayMessage>, ParsePayloadError>, Option<u64>) {
     let seq_num = payload.seq_num;
-    let inner = parse_payload_inner(payload);
+    let inner = match parse_payload_inner(payload) {
+        Err(ParsePayloadError::MalformedEvent { name, raw, error }) => match unparsed_events {
+            true => Ok(Some(GatewayMessage::Event(Event::Unparsed { name, raw, error }))),
+            false => Ok(None),
+        },
+        inner => inner,
+    };
     (inner, seq_num)
 }
 
-const IDENTIFY_TEMPLATE: &'static str = r#"{
+const IDENTIFY_TEMPLATE: &str = r#"{
     "op": 2,
     "d": {
         "token": "TOKEN",
@@ -524,18 +416,18 @@ impl<'a> Resume<'a> {
 }
 
 impl Gateway {
-    
-    fn connect<'a>(token: String, base_url: String, client: &'a TheClient, resume: Option<ResumeInfo>) -> impl Future<Output = Result<Self, GatewayError>> + 'a {
+
+    fn connect<'a>(token: String, base_url: String, client: &'a TheClient, resume: Option<ResumeInfo>, unparsed_events: bool) -> impl Future<Output = Result<Self, GatewayError>> + 'a {
         async move {
-            
-            let gateway_info = get_gateway(&token, &base_url, &client).await?;
+        
+            let gateway_info = get_gateway(&token, &base_url, client).await?;
             
             // dbg!("gateway: {:?}", gateway_info);
             
             let url: &str = gateway_info.get_url();
             let mut url = String::from(url);
             if !url.ends_with("/") {
-                url.push_str("/")
+                url.push('/')
             }
             url.push_str("?v=9&encoding=json");
             let url: &str = &url;
@@ -550,6 +442,7 @@ impl Gateway {
                 resume: resume,
                 seq_num: None,
                 did_resume: None,
+                unparsed_events: unparsed_events,
                 // spawner: spawner,
             };
             
@@ -557,7 +450,13 @@ impl Gateway {
         }
     }
     
-    pub fn recv<'a>(&'a mut self) -> impl Future<Output = Result<GatewayMessage, GatewayError>> + 'a {
+    pub fn recv<'a>(&'a mut self) -> impl Future<Output = Result<GatewayMessage, crate::Error>> + 'a {
+        async move {
+            self.recv_inner().await.map_err(crate::Error::from)
+        }
+    }
+    
+    fn recv_inner<'a>(&'a mut self) -> impl Future<Output = Result<GatewayMessage, GatewayError>> + 'a {
         async move {
             loop {
                 // dbg!("before recv");
@@ -573,8 +472,8 @@ impl Gateway {
                             GatewayError::MalformedPayload
                         })?;
                     
-                    let (payload, seq) = parse_payload(payload);
-                    if let Some(_) = seq {
+                    let (payload, seq) = parse_payload(payload, self.unparsed_events);
+                    if seq.is_some() {
                         self.seq_num = seq
                     }
                     
@@ -586,8 +485,8 @@ impl Gateway {
                         Err(ParsePayloadError::Unkown) => {
                             GatewayMessage::Raw(msg)
                         }
-                        Err(ParsePayloadError::Malformed) => {
-                            return Err(GatewayError::MalformedPayload)
+                        Err(err) => {
+                            return Err(err.into())
                         }
                     };
                     
@@ -656,6 +555,13 @@ async fn send_heartbeat(interval_ms: u64, mut sender: SenderM) {
 pub struct GatewayBuilder {
     base_url_val: String,
     resume: Option<ResumeInfo>,
+    unparsed_events: bool,
+}
+
+impl Default for GatewayBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl GatewayBuilder {
@@ -663,6 +569,7 @@ impl GatewayBuilder {
         GatewayBuilder {
             base_url_val: "https://discordapp.com/api/v9".into(),
             resume: None,
+            unparsed_events: false,
         }
     }
     
@@ -676,7 +583,66 @@ impl GatewayBuilder {
         self
     }
     
-    pub fn connect<'a>(self, token: String, client: &'a TheClient) -> impl Future<Output = Result<Gateway, GatewayError>> + 'a {
-        Gateway::connect(token, self.base_url_val, client, self.resume)
+    /// Deliver dispatches that fail to decode as `Event::Unparsed` instead of
+    /// skipping them.
+    pub fn unparsed_events(mut self, enabled: bool) -> Self {
+        self.unparsed_events = enabled;
+        self
+    }
+    
+    pub fn connect<'a>(self, token: String, client: &'a TheClient) -> impl Future<Output = Result<Gateway, crate::Error>> + 'a {
+        async move {
+            Gateway::connect(token, self.base_url_val, client, self.resume, self.unparsed_events).await
+                .map_err(crate::Error::from)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    fn dispatch(name: &str, seq: u64, data: Value) -> Payload {
+        serde_json::from_value(json!({"op": 0, "s": seq, "t": name, "d": data})).unwrap()
+    }
+    
+    #[test]
+    fn malformed_dispatch_is_unparsed_when_enabled() {
+        let data = json!({"id": "not a snowflake", "channel_id": 5});
+        
+        match parse_payload(dispatch("MESSAGE_CREATE", 42, data.clone()), true) {
+            (Ok(Some(GatewayMessage::Event(Event::Unparsed { name, raw, .. }))), Some(42)) => {
+                assert_eq!(name, "MESSAGE_CREATE");
+                assert_eq!(raw, data);
+            }
+            other => panic!("{:?}", other),
+        }
+        
+        // used to panic on the missing session id
+        let data = json!({"guild_id": "41771983423143937", "user_id": "80351110224678912"});
+        assert!(matches!(parse_payload(dispatch("VOICE_STATE_UPDATE", 43, data), true),
+            (Ok(Some(GatewayMessage::Event(Event::Unparsed { .. }))), Some(43))));
+    }
+    
+    #[test]
+    fn malformed_dispatch_is_skipped_when_disabled() {
+        let data = json!({"id": "not a snowflake", "channel_id": 5});
+        assert!(matches!(parse_payload(dispatch("MESSAGE_CREATE", 42, data), false), (Ok(None), Some(42))));
+    }
+    
+    #[test]
+    fn unknown_dispatch_is_delivered() {
+        let data = json!({"anything": true});
+        
+        for unparsed_events in [true, false] {
+            match parse_payload(dispatch("SOMETHING_NEW", 7, data.clone()), unparsed_events) {
+                (Ok(Some(GatewayMessage::Event(Event::Unknown(name, raw)))), Some(7)) => {
+                    assert_eq!(name, "SOMETHING_NEW");
+                    assert_eq!(raw, data);
+                }
+                other => panic!("{:?}", other),
+            }
+        }
     }
 }
@@ -1,3 +1,4 @@
+#![allow(clippy::manual_async_fn, clippy::redundant_field_names)]
 
 // use bytes::Bytes;
 
@@ -23,25 +24,33 @@ use futures::channel::mpsc::{UnboundedReceiver, UnboundedSender};
 
 pub use crate::send_message::TheClient;
 
-#[derive(Debug, Fail)]
+#[derive(Debug)]
 pub enum WebSocketError {
-    #[fail(display = "Connection Failed: {}", reason)]
     Jank {
         reason: String,
     },
-    #[fail(display = "NoUpgrade: {}", status)]
     NoUpgrade {
         status: StatusCode,
         body: Vec<u8>,
     },
 }
 
+impl std::fmt::Display for WebSocketError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            WebSocketError::Jank { reason } => write!(f, "connection failed: {}", reason),
+            WebSocketError::NoUpgrade { status, .. } => write!(f, "websocket upgrade refused with status {}", status),
+        }
+    }
+}
+
+impl std::error::Error for WebSocketError {}
+
 impl WebSocketError {
     fn jank(reason: String) -> Self {
-        let jank = WebSocketError::Jank {
+        WebSocketError::Jank {
             reason: reason,
-        };
-        jank
+        }
     }
 }
 
@@ -88,7 +97,7 @@ async fn ws_into_io_task(ws: WSStream, mut out_msgs_recv: RecvM, mut in_msg_chan
                     // eprintln!("CCC {:?}", msg);
                     // eprintln!("-- ws send");
                     // ws.send(msg.into());
-                    let fut = (&mut sink).send(msg.into());
+                    let fut = sink.send(msg);
                     // fut.compat().await.unwrap();
                     fut.await.unwrap();
                 } else {
@@ -108,7 +117,7 @@ async fn ws_into_io_task(ws: WSStream, mut out_msgs_recv: RecvM, mut in_msg_chan
                     eprintln!("ws err: {}", err);
                     break;
                 }
-                if let None = in_msg_val {
+                if in_msg_val.is_none() {
                     eprintln!("ws closed: ");
                     break;
                 }
@@ -1,13 +1,21 @@
+#![allow(clippy::manual_async_fn, clippy::needless_lifetimes, clippy::redundant_field_names)]
 
-use futures::Future;
+use std::sync::Arc;
+
+use futures::{Future, Stream};
 
 pub use crate::send_message::TheClient;
 pub use crate::send_message;
 pub use crate::gateway::{Gateway, GatewayBuilder, GatewayMessage, GatewayError};
-pub use crate::discord::{Snowflake, GuildMember};
-pub use crate::send_message::NewMessage;
+pub use crate::discord::{GuildId, ChannelId, UserId, RoleId, MessageId, Message, GuildMember, ReactionType};
+pub use crate::send_message::{NewMessage, EditMessage};
 use crate::set_reaction;
+use crate::Error;
+use crate::ratelimit::{RateLimitBackend, RateLimiter};
+pub use crate::retry::RetryPolicy;
 use crate::discord_api::channel::get_channel;
+use crate::discord_api::channel::messages;
+pub use crate::discord_api::channel::messages::{MessagesAt, HistoryDirection};
 use crate::Channel;
 
 use crate::gateway::GatewayMessage as GM;
@@ -20,25 +28,52 @@ pub struct Discord {
     base_url: String,
     token: String,
     session_id: Option<String>,
+    unparsed_events: bool,
 }
 
 impl Discord {
-    pub fn new(base_url: String, token: String) -> Self {
-        let http_client = crate::send_message::get_client().unwrap();
+    pub fn new(base_url: String, token: String) -> Result<Self, Error> {
+        Self::with_rate_limiter(base_url, token, Arc::new(RateLimiter::new()))
+    }
+    
+    /// Keeps REST rate limits in `backend` instead of this process, e.g. a
+    /// `UnixSocketRateLimiter` to share them with other processes.
+    pub fn with_rate_limiter(base_url: String, token: String, backend: Arc<dyn RateLimitBackend>) -> Result<Self, Error> {
+        let http_client = crate::send_message::get_client_with_rate_limiter(backend)?;
         
-        Self {
+        Ok(Self {
             client: http_client,
             gateway: None,
             base_url: base_url,
             token: token,
             session_id: None,
-        }
+            unparsed_events: false,
+        })
     }
     
-    pub async fn connect(&mut self) -> Result<(), GatewayError> {
-        
+    /// Deliver dispatches that fail to decode as `Event::Unparsed` rather than
+    /// skipping them. Takes effect on the next connect or reconnect.
+    pub fn set_unparsed_events(&mut self, enabled: bool) {
+        self.unparsed_events = enabled;
+    }
+    
+    /// Requests per second allowed across this client and all of its
+    /// `SendHandle`s, defaults to 50.
+    pub async fn set_global_rate_limit(&self, per_second: u32) {
+        self.client.set_global_rate_limit(per_second).await
+    }
+    
+    /// How failed REST requests are retried by this client and all of its
+    /// `SendHandle`s.
+    pub fn set_retry_policy(&self, policy: RetryPolicy) {
+        self.client.set_retry_policy(policy);
+    }
+    
+    pub async fn connect(&mut self) -> Result<(), Error> {
+    
         let gateway: Gateway = GatewayBuilder::new()
             .base_url(self.base_url.clone())
+            .unparsed_events(self.unparsed_events)
             .connect(self.token.clone(), &self.client)
             .await?;
         self.gateway = Some(gateway);
@@ -46,11 +81,11 @@ impl Discord {
         Ok(())
     }
     
-    pub fn send<'a>(&'a self, to: Snowflake, msg: &'a NewMessage) -> impl Future<Output=Result<(), send_message::Error>> + 'a {
+    pub fn send<'a>(&'a self, to: ChannelId, msg: &'a NewMessage) -> impl Future<Output=Result<Vec<Message>, Error>> + 'a {
         send_message::send(to, msg, &self.base_url, &self.token, &self.client)
     }
     
-    pub async fn send_gateway_raw(&mut self, msg: &str) -> Result<(), GatewayError> {
+    pub async fn send_gateway_raw(&mut self, msg: &str) -> Result<(), Error> {
         let gateway = self.gateway.as_mut().unwrap();
         
         gateway.ws.send(msg).await.map_err(|err| {
@@ -69,7 +104,8 @@ impl Discord {
         }
     }
     
-    pub fn recv<'a>(&'a mut self) -> impl Future<Output=Result<GatewayMessage, GatewayError>> + 'a {
+    #[allow(clippy::never_loop)]
+    pub fn recv<'a>(&'a mut self) -> impl Future<Output=Result<GatewayMessage, Error>> + 'a {
         async move {
             let gateway = self.gateway.as_mut().unwrap();
             loop {
@@ -84,7 +120,7 @@ impl Discord {
                         if let Some(ref mut gateway) = self.gateway {
                             gateway.seq_num = None;
                         }
-                        break Err(GatewayError::InvalidSession)
+                        break Err(GatewayError::InvalidSession.into())
                     }
                     Ok(GM::Reconnect) => {
                         let is_reconnect = self.reconnect().await?;
@@ -100,10 +136,11 @@ impl Discord {
         }
     }
     
-    pub async fn reconnect_with(&mut self, session_id: String, seq: u64) -> Result<(), GatewayError> {
+    pub async fn reconnect_with(&mut self, session_id: String, seq: u64) -> Result<(), Error> {
         let gateway: Gateway = GatewayBuilder::new()
             .base_url(self.base_url.clone())
             .resume(session_id.clone(), seq)
+            .unparsed_events(self.unparsed_events)
             .connect(self.token.clone(), &self.client).await?;
         
         self.gateway = Some(gateway);
@@ -111,7 +148,7 @@ impl Discord {
         Ok(())
     }
     
-    pub fn reconnect<'a>(&'a mut self) -> impl Future<Output=Result<bool, GatewayError>> + 'a {
+    pub fn reconnect<'a>(&'a mut self) -> impl Future<Output=Result<bool, Error>> + 'a {
         async move {
             if let (Some(ref session_id), Some(seq)) = (&self.session_id, self.gateway.as_ref().and_then(|g| g.seq_num)) {
                 self.reconnect_with(session_id.clone(), seq).await?;
@@ -141,33 +178,66 @@ pub struct SendHandle {
 }
 
 impl SendHandle {
-    pub fn send<'a>(&'a self, to: Snowflake, msg: &'a NewMessage) -> impl Future<Output=Result<(), send_message::Error>> + 'a {
+    /// Sends `msg` to `to`, returning one message per part when its content
+    /// had to be split to fit discord's length limit.
+    pub fn send<'a>(&'a self, to: ChannelId, msg: &'a NewMessage) -> impl Future<Output=Result<Vec<Message>, Error>> + 'a {
         send_message::send(to, msg, &self.base_url, &self.token, &self.client)
     }
     
-    pub async fn set_reaction(&self, channel: Snowflake, msg: Snowflake, emoji: &str) -> Result<(), send_message::Error> {
-        set_reaction::set_reaction(channel, msg, emoji, &self.base_url, &self.auth, &self.client).await
+    pub async fn set_reaction(&self, channel: ChannelId, msg: MessageId, emoji: &ReactionType) -> Result<(), Error> {
+        set_reaction::set_reaction(channel, msg, emoji, &self.base_url, &self.auth, &self.client).await.map_err(Error::from)
+    }
+    
+    pub async fn edit_message(&self, channel: ChannelId, msg: MessageId, edit: &EditMessage<'_>) -> Result<Message, Error> {
+        messages::edit_message(channel, msg, edit, &self.base_url, &self.auth, &self.client).await.map_err(Error::from)
+    }
+    
+    /// `reason` shows up in the guild's audit log.
+    pub async fn delete_message(&self, channel: ChannelId, msg: MessageId, reason: Option<&str>) -> Result<(), Error> {
+        messages::delete_message(channel, msg, reason, &self.base_url, &self.auth, &self.client).await.map_err(Error::from)
+    }
+    
+    /// Deletes 2 to 100 distinct messages that are at most 14 days old.
+    pub async fn bulk_delete_messages(&self, channel: ChannelId, msgs: &[MessageId], reason: Option<&str>) -> Result<(), Error> {
+        messages::bulk_delete_messages(channel, msgs, reason, &self.base_url, &self.auth, &self.client).await.map_err(Error::from)
+    }
+    
+    pub async fn crosspost_message(&self, channel: ChannelId, msg: MessageId) -> Result<Message, Error> {
+        messages::crosspost_message(channel, msg, &self.base_url, &self.auth, &self.client).await.map_err(Error::from)
+    }
+    
+    /// Gets up to 100 messages, newest first.
+    pub async fn get_messages(&self, channel: ChannelId, at: MessagesAt, limit: u8) -> Result<Vec<Message>, Error> {
+        messages::get_messages(channel, at, limit, &self.base_url, &self.auth, &self.client).await.map_err(Error::from)
+    }
+    
+    /// Pages through every message in `channel`, ending after the first error.
+    pub fn message_history(&self, channel: ChannelId, direction: HistoryDirection) -> impl Stream<Item=Result<Message, Error>> {
+        use futures::StreamExt;
+        
+        messages::message_history(channel, direction, self.base_url.clone(), self.auth.clone(), self.client.clone())
+            .map(|res| res.map_err(Error::from))
     }
     
-    pub async fn get_channel(&self, channel: Snowflake) -> Result<Channel, send_message::Error> {
-        get_channel(channel, &self.base_url, &self.auth, &self.client).await
+    pub async fn get_channel(&self, channel: ChannelId) -> Result<Channel, Error> {
+        get_channel(channel, &self.base_url, &self.auth, &self.client).await.map_err(Error::from)
     }
     
-    pub async fn get_guild_members(&self, guild: Snowflake) -> Result<Vec<GuildMember>, send_message::Error> {
+    pub async fn get_guild_members(&self, guild: GuildId) -> Result<Vec<GuildMember>, Error> {
         crate::discord_api::guild::get_members(
             guild, &self.base_url, &self.auth, &self.client
-        ).await
+        ).await.map_err(Error::from)
     }
     
-    pub async fn add_member_role(&self, guild: Snowflake, user: Snowflake, role: Snowflake) -> Result<(), send_message::Error> {
+    pub async fn add_member_role(&self, guild: GuildId, user: UserId, role: RoleId) -> Result<(), Error> {
         crate::discord_api::guild::roles::add_member_role(
             guild, user, role, &self.base_url, &self.auth, &self.client
-        ).await
+        ).await.map_err(Error::from)
     }
     
-    pub async fn remove_member_role(&self, guild: Snowflake, user: Snowflake, role: Snowflake) -> Result<(), send_message::Error> {
+    pub async fn remove_member_role(&self, guild: GuildId, user: UserId, role: RoleId) -> Result<(), Error> {
         crate::discord_api::guild::roles::remove_member_role(
             guild, user, role, &self.base_url, &self.auth, &self.client
-        ).await
+        ).await.map_err(Error::from)
     }
 }
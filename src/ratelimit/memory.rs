@@ -0,0 +1,419 @@
+#![allow(clippy::redundant_field_names)]
+
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::Entry;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::future::{self, BoxFuture, FutureExt};
+use tokio::sync::Notify;
+use tokio::time::{sleep_until, timeout_at, Instant};
+
+use crate::discord::Snowflake;
+
+use super::{Route, BucketInfo, RateLimitBackend};
+
+#[derive(Debug)]
+struct Bucket {
+    limit: u64,
+    remaining: u64,
+    reset_at: Instant,
+    // how long the last reported window was, used to guess the next one
+    // until a response tells us
+    window: Duration,
+}
+
+// the one request let through to a route and major parameter whose limits
+// aren't known yet, the others wait for its response
+#[derive(Debug)]
+struct Probe {
+    done: Arc<Notify>,
+    // in case the request is dropped and never reports back
+    expires: Instant,
+}
+
+/// How long requests to a new route wait for the first one's response before
+/// another is let through.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub const DEFAULT_GLOBAL_LIMIT: u32 = 50;
+
+// requests per second allowed across all routes
+#[derive(Debug)]
+struct Global {
+    per_second: u32,
+    used: u32,
+    window_start: Instant,
+    // set by a global 429
+    paused_until: Option<Instant>,
+}
+
+impl Global {
+    // the instant to wait for, `None` when the request may go now
+    fn take(&mut self, now: Instant) -> Option<Instant> {
+        if let Some(until) = self.paused_until {
+            if until > now {
+                return Some(until)
+            }
+            self.paused_until = None;
+        }
+        
+        if now >= self.window_start + Duration::from_secs(1) {
+            self.window_start = now;
+            self.used = 0;
+        }
+        
+        if self.used < self.per_second {
+            self.used += 1;
+            None
+        } else {
+            Some(self.window_start + Duration::from_secs(1))
+        }
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    // bucket hash discord reported for each route
+    routes: HashMap<String, String>,
+    // limits are shared by routes with the same bucket hash and major parameter
+    buckets: HashMap<(String, Snowflake), Bucket>,
+    probes: HashMap<(String, Snowflake), Probe>,
+    // routes that answered without limits
+    unlimited: HashSet<(String, Snowflake)>,
+    global: Global,
+}
+
+enum Wait {
+    Until(Instant),
+    Probe(Arc<Notify>, Instant),
+}
+
+/// Tracks discord's per route rate limits from the `X-RateLimit-*` response
+/// headers and holds requests back until their bucket resets instead of
+/// running into 429s.
+///
+/// Also keeps all requests under the global limit of requests per second and
+/// stops every request while discord reports it being exceeded.
+///
+/// The default backend, limits are only shared by clients in this process.
+#[derive(Debug)]
+pub struct RateLimiter {
+    state: Mutex<State>,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::with_global_limit(DEFAULT_GLOBAL_LIMIT)
+    }
+    
+    /// For bots discord allows more than the default 50 requests per second.
+    pub fn with_global_limit(per_second: u32) -> Self {
+        RateLimiter {
+            state: Mutex::new(State {
+                routes: HashMap::new(),
+                buckets: HashMap::new(),
+                probes: HashMap::new(),
+                unlimited: HashSet::new(),
+                global: Global {
+                    per_second: per_second.max(1),
+                    used: 0,
+                    window_start: Instant::now(),
+                    paused_until: None,
+                },
+            }),
+        }
+    }
+    
+    pub fn set_global_limit(&self, per_second: u32) {
+        self.state.lock().unwrap().global.per_second = per_second.max(1);
+    }
+    
+    /// Waits until a request to `route` can be made without being limited and
+    /// takes it from its bucket and the global limit.
+    ///
+    /// Until a response reports the limits of a route and major parameter
+    /// only one request to it is let through at a time.
+    pub async fn acquire(&self, route: &Route) {
+        let key = (route.path().to_string(), route.major());
+        
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                let state = &mut *state;
+                let now = Instant::now();
+                
+                let mut bucket = state.routes.get(route.path())
+                    .and_then(|hash| state.buckets.get_mut(&(hash.clone(), route.major())));
+                
+                if let Some(ref mut bucket) = bucket {
+                    if bucket.reset_at <= now {
+                        bucket.remaining = bucket.limit;
+                        bucket.reset_at = now + bucket.window;
+                    }
+                }
+                
+                match bucket {
+                    Some(bucket) if bucket.remaining == 0 => Wait::Until(bucket.reset_at),
+                    Some(bucket) => match state.global.take(now) {
+                        Some(wait) => Wait::Until(wait),
+                        None => {
+                            bucket.remaining -= 1;
+                            return
+                        }
+                    },
+                    None => match state.probes.get(&key) {
+                        Some(probe) if probe.expires > now && !state.unlimited.contains(&key) => {
+                            Wait::Probe(probe.done.clone(), probe.expires)
+                        }
+                        _ => match state.global.take(now) {
+                            Some(wait) => Wait::Until(wait),
+                            None => {
+                                if !state.unlimited.contains(&key) {
+                                    state.probes.insert(key, Probe {
+                                        done: Arc::new(Notify::new()),
+                                        expires: now + PROBE_TIMEOUT,
+                                    });
+                                }
+                                return
+                            }
+                        },
+                    },
+                }
+            };
+            
+            match wait {
+                Wait::Until(instant) => sleep_until(instant).await,
+                Wait::Probe(done, expires) => {
+                    // registered before the lock was released, so a response
+                    // in between still wakes it
+                    let _ = timeout_at(expires, done.notified_owned()).await;
+                }
+            }
+        }
+    }
+    
+    /// Records the limits discord reported in the response to a request to
+    /// `route`, `None` if the request finished without reporting any.
+    pub fn update(&self, route: &Route, info: Option<BucketInfo>) {
+        let mut state = self.state.lock().unwrap();
+        let key = (route.path().to_string(), route.major());
+        
+        if let Some(probe) = state.probes.remove(&key) {
+            probe.done.notify_waiters();
+        }
+        
+        let info = match info {
+            Some(info) => info,
+            None => {
+                state.unlimited.insert(key);
+                return
+            }
+        };
+        
+        state.unlimited.remove(&key);
+        state.routes.insert(key.0, info.bucket.clone());
+        
+        let now = Instant::now();
+        match state.buckets.entry((info.bucket, route.major())) {
+            Entry::Occupied(mut entry) => {
+                let bucket = entry.get_mut();
+                // a slow response reports what was left when discord handled
+                // it, not counting requests we sent since
+                if bucket.reset_at > now {
+                    bucket.remaining = bucket.remaining.min(info.remaining);
+                } else {
+                    bucket.remaining = info.remaining;
+                }
+                bucket.limit = info.limit;
+                bucket.reset_at = now + info.reset_after;
+                bucket.window = info.reset_after;
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(Bucket {
+                    limit: info.limit,
+                    remaining: info.remaining,
+                    reset_at: now + info.reset_after,
+                    window: info.reset_after,
+                });
+            }
+        }
+    }
+    
+    /// Lets the next request to a route with unknown limits through after the
+    /// one probing it got no response, without treating it as unlimited.
+    pub fn failed(&self, route: &Route) {
+        let mut state = self.state.lock().unwrap();
+        let key = (route.path().to_string(), route.major());
+        
+        if let Some(probe) = state.probes.remove(&key) {
+            probe.done.notify_waiters();
+        }
+    }
+    
+    /// Empties the bucket of `route` for `retry_after` after a 429.
+    pub fn limited(&self, route: &Route, retry_after: Duration) {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        if let Some(bucket) = state.routes.get(route.path())
+            .and_then(|hash| state.buckets.get_mut(&(hash.clone(), route.major())))
+        {
+            bucket.remaining = 0;
+            bucket.reset_at = Instant::now() + retry_after;
+        }
+    }
+    
+    /// Stops all requests for `retry_after` after a global 429.
+    pub fn global_limited(&self, retry_after: Duration) {
+        let mut state = self.state.lock().unwrap();
+        let until = Instant::now() + retry_after;
+        if state.global.paused_until.is_none_or(|paused| paused < until) {
+            state.global.paused_until = Some(until);
+        }
+    }
+}
+
+impl RateLimitBackend for RateLimiter {
+    fn acquire<'a>(&'a self, route: &'a Route) -> BoxFuture<'a, ()> {
+        RateLimiter::acquire(self, route).boxed()
+    }
+    
+    fn update<'a>(&'a self, route: &'a Route, bucket: Option<BucketInfo>) -> BoxFuture<'a, ()> {
+        RateLimiter::update(self, route, bucket);
+        future::ready(()).boxed()
+    }
+    
+    fn failed<'a>(&'a self, route: &'a Route) -> BoxFuture<'a, ()> {
+        RateLimiter::failed(self, route);
+        future::ready(()).boxed()
+    }
+    
+    fn limited<'a>(&'a self, route: &'a Route, retry_after: Duration) -> BoxFuture<'a, ()> {
+        RateLimiter::limited(self, route, retry_after);
+        future::ready(()).boxed()
+    }
+    
+    fn global_limited(&self, retry_after: Duration) -> BoxFuture<'_, ()> {
+        RateLimiter::global_limited(self, retry_after);
+        future::ready(()).boxed()
+    }
+    
+    fn set_global_limit(&self, per_second: u32) -> BoxFuture<'_, ()> {
+        RateLimiter::set_global_limit(self, per_second);
+        future::ready(()).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    use tokio::time::{advance, timeout};
+    
+    fn route(channel: u64) -> Route {
+        Route::new("POST /channels/{channel}/messages", channel)
+    }
+    
+    fn info(remaining: u64, reset_after: u64) -> Option<BucketInfo> {
+        Some(BucketInfo {
+            bucket: "abcd".into(),
+            limit: 5,
+            remaining: remaining,
+            reset_after: Duration::from_secs(reset_after),
+        })
+    }
+    
+    // whether acquire returns without waiting
+    async fn acquired(limiter: &RateLimiter, route: &Route) -> bool {
+        timeout(Duration::from_millis(1), limiter.acquire(route)).await.is_ok()
+    }
+    
+    #[tokio::test(start_paused = true)]
+    async fn one_request_to_unknown_route() {
+        let limiter = Arc::new(RateLimiter::new());
+        
+        assert!(acquired(&limiter, &route(1)).await);
+        assert!(!acquired(&limiter, &route(1)).await);
+        // other channels have their own limits
+        assert!(acquired(&limiter, &route(2)).await);
+        
+        let waiting = tokio::spawn({
+            let limiter = limiter.clone();
+            async move { limiter.acquire(&route(1)).await }
+        });
+        tokio::task::yield_now().await;
+        assert!(!waiting.is_finished());
+        
+        limiter.update(&route(1), info(1, 5));
+        timeout(Duration::from_millis(1), waiting).await.unwrap().unwrap();
+        
+        // the response left one request, which the waiting one took
+        assert!(!acquired(&limiter, &route(1)).await);
+        advance(Duration::from_secs(5)).await;
+        assert!(acquired(&limiter, &route(1)).await);
+    }
+    
+    #[tokio::test(start_paused = true)]
+    async fn unknown_route_without_limits() {
+        let limiter = RateLimiter::new();
+        
+        assert!(acquired(&limiter, &route(1)).await);
+        limiter.update(&route(1), None);
+        assert!(acquired(&limiter, &route(1)).await);
+        assert!(acquired(&limiter, &route(1)).await);
+    }
+    
+    #[tokio::test(start_paused = true)]
+    async fn failed_probe_stays_limited() {
+        let limiter = RateLimiter::new();
+        
+        assert!(acquired(&limiter, &route(1)).await);
+        assert!(!acquired(&limiter, &route(1)).await);
+        
+        // the next request probes the route again, the one after it waits
+        limiter.failed(&route(1));
+        assert!(acquired(&limiter, &route(1)).await);
+        assert!(!acquired(&limiter, &route(1)).await);
+    }
+    
+    #[tokio::test(start_paused = true)]
+    async fn dropped_probe_expires() {
+        let limiter = RateLimiter::new();
+        
+        assert!(acquired(&limiter, &route(1)).await);
+        assert!(!acquired(&limiter, &route(1)).await);
+        
+        advance(PROBE_TIMEOUT).await;
+        assert!(acquired(&limiter, &route(1)).await);
+    }
+    
+    #[tokio::test(start_paused = true)]
+    async fn late_responses_dont_refill() {
+        let limiter = RateLimiter::new();
+        
+        assert!(acquired(&limiter, &route(1)).await);
+        limiter.update(&route(1), info(4, 5));
+        
+        for _ in 0..4 {
+            assert!(acquired(&limiter, &route(1)).await);
+        }
+        // responses to the earlier requests arrive after the bucket ran dry
+        limiter.update(&route(1), info(3, 5));
+        limiter.update(&route(1), info(2, 5));
+        assert!(!acquired(&limiter, &route(1)).await);
+        
+        // a new window starts over
+        advance(Duration::from_secs(5)).await;
+        limiter.update(&route(1), info(4, 5));
+        for _ in 0..4 {
+            assert!(acquired(&limiter, &route(1)).await);
+        }
+        assert!(!acquired(&limiter, &route(1)).await);
+    }
+}
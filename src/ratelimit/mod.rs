@@ -0,0 +1,108 @@
+
+use std::borrow::Cow;
+use std::fmt;
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use reqwest::header::HeaderMap;
+
+use crate::discord::Snowflake;
+
+mod memory;
+pub use self::memory::{RateLimiter, DEFAULT_GLOBAL_LIMIT};
+
+#[cfg(unix)]
+mod unix;
+#[cfg(unix)]
+pub use self::unix::{UnixSocketRateLimiter, serve_unix};
+
+/// A REST endpoint as discord groups them for rate limiting, the path with
+/// every id but the major parameter (channel, guild or webhook) left out.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Route {
+    path: Cow<'static, str>,
+    major: Snowflake,
+}
+
+impl Route {
+    pub fn new<P, T>(path: P, major: T) -> Self
+        where P: Into<Cow<'static, str>>,
+              T: Into<Snowflake>,
+    {
+        Route {
+            path: path.into(),
+            major: major.into(),
+        }
+    }
+    
+    /// The method and path, e.g. `POST /channels/{channel}/messages`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+    
+    pub fn major(&self) -> Snowflake {
+        self.major
+    }
+}
+
+impl fmt::Display for Route {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.path, self.major)
+    }
+}
+
+/// The limits of a bucket as reported in a response's `X-RateLimit-*` headers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BucketInfo {
+    /// Discord's id for the bucket, routes with the same id share limits.
+    pub bucket: String,
+    pub limit: u64,
+    pub remaining: u64,
+    pub reset_after: Duration,
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+impl BucketInfo {
+    /// `None` for routes without limits, which don't send the headers.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let reset_after: f64 = header(headers, "x-ratelimit-reset-after")?.parse().ok()?;
+        
+        Some(BucketInfo {
+            bucket: header(headers, "x-ratelimit-bucket")?.to_string(),
+            limit: header(headers, "x-ratelimit-limit")?.parse().ok()?,
+            remaining: header(headers, "x-ratelimit-remaining")?.parse().ok()?,
+            reset_after: Duration::from_secs_f64(reset_after),
+        })
+    }
+}
+
+/// Where REST rate limits are kept. `RateLimiter` keeps them in this process,
+/// other backends let several processes using the same token share them.
+///
+/// Backends should let requests through when they fail, discord's 429s are
+/// still handled.
+pub trait RateLimitBackend: fmt::Debug + Send + Sync {
+    /// Waits until a request to `route` can be made without being limited and
+    /// takes it from its bucket and the global limit.
+    fn acquire<'a>(&'a self, route: &'a Route) -> BoxFuture<'a, ()>;
+    
+    /// Records the limits discord reported in the response to a request to
+    /// `route`, `None` when the response had no rate limit headers.
+    fn update<'a>(&'a self, route: &'a Route, bucket: Option<BucketInfo>) -> BoxFuture<'a, ()>;
+    
+    /// Called instead of `update` when a request to `route` got no response
+    /// from discord, so nothing was learned about its limits.
+    fn failed<'a>(&'a self, route: &'a Route) -> BoxFuture<'a, ()>;
+    
+    /// Empties the bucket of `route` for `retry_after` after a 429.
+    fn limited<'a>(&'a self, route: &'a Route, retry_after: Duration) -> BoxFuture<'a, ()>;
+    
+    /// Stops all requests for `retry_after` after a global 429.
+    fn global_limited(&self, retry_after: Duration) -> BoxFuture<'_, ()>;
+    
+    /// Changes how many requests per second are allowed across all routes.
+    fn set_global_limit(&self, per_second: u32) -> BoxFuture<'_, ()>;
+}
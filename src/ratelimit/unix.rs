@@ -0,0 +1,282 @@
+
+use std::io;
+use std::os::unix::fs::FileTypeExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future::{BoxFuture, FutureExt};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+
+use super::{Route, BucketInfo, RateLimitBackend, RateLimiter};
+
+/// A backend keeping the limits in a local ratelimit service, so every
+/// process connecting to the same socket shares them. `serve_unix` runs such
+/// a service.
+///
+/// Every call opens a connection to the socket, writes one line and waits for
+/// one line in reply, `OK` or `ERR <reason>`. The reply to `ACQUIRE` is held
+/// back until the request may be made. Durations are in seconds.
+///
+/// ```text
+/// ACQUIRE <method> <path> <major>
+/// UPDATE <method> <path> <major> [<bucket> <limit> <remaining> <reset_after>]
+/// FAILED <method> <path> <major>
+/// LIMITED <method> <path> <major> <retry_after>
+/// GLOBAL <retry_after>
+/// GLOBAL_LIMIT <per_second>
+/// ```
+#[derive(Debug, Clone)]
+pub struct UnixSocketRateLimiter {
+    path: PathBuf,
+}
+
+impl UnixSocketRateLimiter {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        UnixSocketRateLimiter {
+            path: path.into(),
+        }
+    }
+    
+    async fn request(&self, line: String) -> io::Result<()> {
+        let mut stream = BufReader::new(UnixStream::connect(&self.path).await?);
+        
+        stream.get_mut().write_all(line.as_bytes()).await?;
+        stream.get_mut().write_all(b"\n").await?;
+        
+        let mut reply = String::new();
+        stream.read_line(&mut reply).await?;
+        
+        match reply.trim_end() {
+            "OK" => Ok(()),
+            "" => Err(io::Error::new(io::ErrorKind::UnexpectedEof, "ratelimit service closed the connection")),
+            other => Err(io::Error::other(other.to_string())),
+        }
+    }
+    
+    // a broken service shouldn't stop all requests, discord's 429s still
+    // keep us in check
+    fn send(&self, line: String) -> BoxFuture<'_, ()> {
+        async move {
+            if let Err(err) = self.request(line).await {
+                eprintln!("ratelimit service at {}: {}", self.path.display(), err);
+            }
+        }.boxed()
+    }
+}
+
+impl RateLimitBackend for UnixSocketRateLimiter {
+    fn acquire<'a>(&'a self, route: &'a Route) -> BoxFuture<'a, ()> {
+        self.send(format!("ACQUIRE {} {}", route.path(), route.major()))
+    }
+    
+    fn update<'a>(&'a self, route: &'a Route, bucket: Option<BucketInfo>) -> BoxFuture<'a, ()> {
+        match bucket {
+            Some(bucket) => self.send(format!("UPDATE {} {} {} {} {} {}",
+                route.path(), route.major(),
+                bucket.bucket, bucket.limit, bucket.remaining, bucket.reset_after.as_secs_f64())),
+            None => self.send(format!("UPDATE {} {}", route.path(), route.major())),
+        }
+    }
+    
+    fn failed<'a>(&'a self, route: &'a Route) -> BoxFuture<'a, ()> {
+        self.send(format!("FAILED {} {}", route.path(), route.major()))
+    }
+    
+    fn limited<'a>(&'a self, route: &'a Route, retry_after: Duration) -> BoxFuture<'a, ()> {
+        self.send(format!("LIMITED {} {} {}", route.path(), route.major(), retry_after.as_secs_f64()))
+    }
+    
+    fn global_limited(&self, retry_after: Duration) -> BoxFuture<'_, ()> {
+        self.send(format!("GLOBAL {}", retry_after.as_secs_f64()))
+    }
+    
+    fn set_global_limit(&self, per_second: u32) -> BoxFuture<'_, ()> {
+        self.send(format!("GLOBAL_LIMIT {}", per_second))
+    }
+}
+
+fn parse_route(method: &str, path: &str, major: &str) -> Option<Route> {
+    Some(Route::new(format!("{} {}", method, path), major.parse::<u64>().ok()?))
+}
+
+fn parse_secs(secs: &str) -> Option<Duration> {
+    secs.parse().ok().and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+}
+
+async fn handle_command(limiter: &RateLimiter, line: &str) -> Option<()> {
+    let args: Vec<&str> = line.split_whitespace().collect();
+    
+    match args[..] {
+        ["ACQUIRE", method, path, major] => {
+            limiter.acquire(&parse_route(method, path, major)?).await;
+        }
+        ["UPDATE", method, path, major, bucket, limit, remaining, reset_after] => {
+            limiter.update(&parse_route(method, path, major)?, Some(BucketInfo {
+                bucket: bucket.to_string(),
+                limit: limit.parse().ok()?,
+                remaining: remaining.parse().ok()?,
+                reset_after: parse_secs(reset_after)?,
+            }));
+        }
+        ["UPDATE", method, path, major] => {
+            limiter.update(&parse_route(method, path, major)?, None);
+        }
+        ["FAILED", method, path, major] => {
+            limiter.failed(&parse_route(method, path, major)?);
+        }
+        ["LIMITED", method, path, major, retry_after] => {
+            limiter.limited(&parse_route(method, path, major)?, parse_secs(retry_after)?);
+        }
+        ["GLOBAL", retry_after] => {
+            limiter.global_limited(parse_secs(retry_after)?);
+        }
+        ["GLOBAL_LIMIT", per_second] => {
+            limiter.set_global_limit(per_second.parse().ok()?);
+        }
+        _ => return None,
+    }
+    
+    Some(())
+}
+
+async fn handle_connection(stream: UnixStream, limiter: Arc<RateLimiter>) -> io::Result<()> {
+    let mut stream = BufReader::new(stream);
+    let mut line = String::new();
+    
+    while stream.read_line(&mut line).await? > 0 {
+        let reply = match handle_command(&limiter, &line).await {
+            Some(()) => "OK\n".to_string(),
+            None => format!("ERR invalid command {:?}\n", line.trim_end()),
+        };
+        stream.get_mut().write_all(reply.as_bytes()).await?;
+        line.clear();
+    }
+    
+    Ok(())
+}
+
+/// Runs a ratelimit service for `UnixSocketRateLimiter`s on a socket at `path`.
+///
+/// A socket left at `path` by a service that didn't shut down cleanly is
+/// replaced, one that is still being served is not.
+pub async fn serve_unix<P: AsRef<Path>>(path: P, limiter: Arc<RateLimiter>) -> Result<(), crate::Error> {
+    serve_unix_inner(path.as_ref(), limiter).await.map_err(crate::Error::RateLimitService)
+}
+
+async fn serve_unix_inner(path: &Path, limiter: Arc<RateLimiter>) -> io::Result<()> {
+    let is_socket = path.symlink_metadata()
+        .map(|meta| meta.file_type().is_socket())
+        .unwrap_or(false);
+    if is_socket && UnixStream::connect(path).await.is_err() {
+        std::fs::remove_file(path)?;
+    }
+    
+    let listener = UnixListener::bind(path)?;
+    
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let limiter = limiter.clone();
+        
+        tokio::spawn(async move {
+            if let Err(err) = handle_connection(stream, limiter).await {
+                eprintln!("ratelimit service connection: {}", err);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    use std::time::Instant;
+    
+    use tokio::time::{sleep, timeout};
+    
+    fn route(channel: u64) -> Route {
+        Route::new("POST /channels/{channel}/messages", channel)
+    }
+    
+    // whether acquire returns without waiting on the service
+    async fn acquired(client: &UnixSocketRateLimiter, route: &Route) -> bool {
+        timeout(Duration::from_millis(100), client.acquire(route)).await.is_ok()
+    }
+    
+    async fn wait_for_service(path: &Path) {
+        for _ in 0..100 {
+            if UnixStream::connect(path).await.is_ok() {
+                return;
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+        panic!("ratelimit service didn't start");
+    }
+    
+    #[tokio::test]
+    async fn line_protocol() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ratelimit.sock");
+        
+        // left behind by a service that crashed
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        
+        tokio::spawn(serve_unix(path.clone(), Arc::new(RateLimiter::new())));
+        wait_for_service(&path).await;
+        let client = UnixSocketRateLimiter::new(&path);
+        
+        // one request probes the unknown route, the next waits for its headers
+        assert!(acquired(&client, &route(1)).await);
+        assert!(!acquired(&client, &route(1)).await);
+        client.update(&route(1), Some(BucketInfo {
+            bucket: "abcd".into(),
+            limit: 5,
+            remaining: 4,
+            reset_after: Duration::from_secs(60),
+        })).await;
+        assert!(acquired(&client, &route(1)).await);
+        
+        // no headers, the route isn't limited
+        assert!(acquired(&client, &route(2)).await);
+        client.update(&route(2), None).await;
+        assert!(acquired(&client, &route(2)).await);
+        assert!(acquired(&client, &route(2)).await);
+        
+        // no response, the next request probes again
+        assert!(acquired(&client, &route(3)).await);
+        client.failed(&route(3)).await;
+        assert!(acquired(&client, &route(3)).await);
+        assert!(!acquired(&client, &route(3)).await);
+        
+        let start = Instant::now();
+        client.limited(&route(1), Duration::from_millis(300)).await;
+        client.acquire(&route(1)).await;
+        assert!(start.elapsed() >= Duration::from_millis(250));
+        
+        let start = Instant::now();
+        client.global_limited(Duration::from_millis(300)).await;
+        client.acquire(&route(2)).await;
+        assert!(start.elapsed() >= Duration::from_millis(250));
+        
+        client.request("GLOBAL_LIMIT 10".into()).await.unwrap();
+        assert!(client.request("UPDATE POST /channels 1 abcd".into()).await.is_err());
+        assert!(client.request("GLOBAL soon".into()).await.is_err());
+        assert!(client.request("".into()).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn live_socket_is_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ratelimit.sock");
+        
+        tokio::spawn(serve_unix(path.clone(), Arc::new(RateLimiter::new())));
+        wait_for_service(&path).await;
+        
+        match serve_unix(&path, Arc::new(RateLimiter::new())).await {
+            Err(crate::Error::RateLimitService(err)) => assert_eq!(err.kind(), io::ErrorKind::AddrInUse),
+            other => panic!("{:?}", other),
+        }
+        assert!(acquired(&UnixSocketRateLimiter::new(&path), &route(1)).await);
+    }
+}
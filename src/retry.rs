@@ -0,0 +1,139 @@
+
+use std::time::Duration;
+
+use rand::Rng;
+use reqwest::Method;
+
+/// When to retry REST requests that failed with a 5xx status or a network
+/// error. Rate limited requests are always retried.
+///
+/// Idempotent requests (GET, PUT, DELETE) are retried by default, POSTs such
+/// as sending a message could end up being done twice so they are only
+/// retried after `retry_posts(true)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    retry_posts: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+            retry_posts: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Never retry failed requests.
+    pub fn none() -> Self {
+        Self::default().max_retries(0)
+    }
+    
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+    
+    /// The delay before the first retry, doubled for every following one.
+    pub fn base_delay(mut self, delay: Duration) -> Self {
+        self.base_delay = delay;
+        self
+    }
+    
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+    
+    /// Also retry POST and PATCH requests.
+    pub fn retry_posts(mut self, enabled: bool) -> Self {
+        self.retry_posts = enabled;
+        self
+    }
+    
+    /// How long to wait before retrying a request after its `attempt`th
+    /// failure, `None` if it shouldn't be retried.
+    pub(crate) fn delay(&self, method: &Method, attempt: u32) -> Option<Duration> {
+        if attempt >= self.max_retries || !(self.retry_posts || method.is_idempotent()) {
+            return None
+        }
+        
+        let backoff = self.base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        
+        // wait between half and all of the backoff so clients that failed
+        // together don't all retry together
+        Some(backoff.mul_f64(rand::thread_rng().gen_range(0.5..=1.0)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn delays(policy: &RetryPolicy, method: &Method) -> Vec<Option<Duration>> {
+        (0..6).map(|attempt| policy.delay(method, attempt)).collect()
+    }
+    
+    // every delay is between half and all of its backoff
+    fn assert_jittered(delay: Option<Duration>, backoff: Duration) {
+        let delay = delay.expect("retried");
+        assert!(delay >= backoff / 2 && delay <= backoff, "{:?} for a backoff of {:?}", delay, backoff);
+    }
+    
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let policy = RetryPolicy::new()
+            .max_retries(5)
+            .base_delay(Duration::from_millis(100))
+            .max_delay(Duration::from_millis(500));
+        let delays = delays(&policy, &Method::GET);
+        
+        assert_jittered(delays[0], Duration::from_millis(100));
+        assert_jittered(delays[1], Duration::from_millis(200));
+        assert_jittered(delays[2], Duration::from_millis(400));
+        assert_jittered(delays[3], Duration::from_millis(500));
+        assert_jittered(delays[4], Duration::from_millis(500));
+        assert_eq!(delays[5], None);
+    }
+    
+    #[test]
+    fn max_retries() {
+        let default = delays(&RetryPolicy::default(), &Method::GET);
+        assert!(default[..3].iter().all(Option::is_some));
+        assert!(default[3..].iter().all(Option::is_none));
+        
+        assert!(delays(&RetryPolicy::none(), &Method::GET).iter().all(Option::is_none));
+        
+        // doesn't overflow after many attempts
+        let policy = RetryPolicy::new().max_retries(u32::MAX);
+        assert_jittered(policy.delay(&Method::GET, 100), Duration::from_secs(10));
+    }
+    
+    #[test]
+    fn posts_only_retried_when_enabled() {
+        let policy = RetryPolicy::new();
+        for method in [Method::GET, Method::PUT, Method::DELETE] {
+            assert!(policy.delay(&method, 0).is_some());
+        }
+        for method in [Method::POST, Method::PATCH] {
+            assert_eq!(policy.delay(&method, 0), None);
+        }
+        
+        let policy = policy.retry_posts(true);
+        for method in [Method::POST, Method::PATCH] {
+            assert!(policy.delay(&method, 0).is_some());
+        }
+    }
+}
@@ -1,25 +1,362 @@
+#![allow(clippy::redundant_field_names)]
 
 use std::borrow::Cow;
+use std::fmt;
+use std::path::PathBuf;
+
+use bytes::Bytes;
 
 fn own_cow(cow: Cow<'_, str>) -> Cow<'static, str> {
     Cow::Owned(cow.into_owned())
 }
 
+fn own_opt_cow(cow: Option<Cow<'_, str>>) -> Option<Cow<'static, str>> {
+    cow.map(own_cow)
+}
+
+pub const EMBED_TITLE_LIMIT: usize = 256;
+pub const EMBED_DESCRIPTION_LIMIT: usize = 4096;
+pub const EMBED_FIELDS_LIMIT: usize = 25;
+pub const EMBED_FIELD_NAME_LIMIT: usize = 256;
+pub const EMBED_FIELD_VALUE_LIMIT: usize = 1024;
+pub const EMBED_FOOTER_LIMIT: usize = 2048;
+pub const EMBED_AUTHOR_LIMIT: usize = 256;
+/// The most characters in all text of all embeds of a message.
+pub const EMBED_TOTAL_LIMIT: usize = 6000;
+pub const EMBEDS_PER_MESSAGE_LIMIT: usize = 10;
+
+/// An embed breaking one of discord's limits, lengths are in characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmbedError {
+    TooLong {
+        /// e.g. `embeds[0].fields[2].value`.
+        field: String,
+        length: usize,
+        max: usize,
+    },
+    TooManyFields(usize),
+    TooManyEmbeds(usize),
+    /// All text of all embeds together is over `EMBED_TOTAL_LIMIT`.
+    TotalTooLong(usize),
+    /// Text discord requires is empty, e.g. `embeds[0].fields[1].name`.
+    Missing(String),
+    /// The embed at this path has nothing set.
+    Empty(String),
+}
+
+impl fmt::Display for EmbedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EmbedError::TooLong { field, length, max } => {
+                write!(f, "{} is {} characters, at most {} are allowed", field, length, max)
+            }
+            EmbedError::TooManyFields(count) => {
+                write!(f, "embed has {} fields, at most {} are allowed", count, EMBED_FIELDS_LIMIT)
+            }
+            EmbedError::TooManyEmbeds(count) => {
+                write!(f, "message has {} embeds, at most {} are allowed", count, EMBEDS_PER_MESSAGE_LIMIT)
+            }
+            EmbedError::TotalTooLong(length) => {
+                write!(f, "embeds are {} characters in total, at most {} are allowed", length, EMBED_TOTAL_LIMIT)
+            }
+            EmbedError::Missing(field) => write!(f, "{} is empty", field),
+            EmbedError::Empty(embed) => {
+                write!(f, "{} has nothing set", embed)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EmbedError {}
+
+/// A bulk delete discord would reject as a whole.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BulkDeleteError {
+    /// Only 2 to 100 messages can be deleted at once.
+    Count(usize),
+    Duplicate(MessageId),
+    /// Older than `BULK_DELETE_MAX_AGE`.
+    TooOld(MessageId),
+}
+
+impl fmt::Display for BulkDeleteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BulkDeleteError::Count(count) => {
+                write!(f, "can only bulk delete 2 to 100 messages, got {}", count)
+            }
+            BulkDeleteError::Duplicate(msg) => write!(f, "message {} is listed more than once", msg),
+            BulkDeleteError::TooOld(msg) => write!(f, "message {} is too old to bulk delete", msg),
+        }
+    }
+}
+
+impl std::error::Error for BulkDeleteError {}
+
+// the footer and author are only sent once they have text, an icon or url
+// set before that waits for it
+#[derive(Debug, Clone, Default, Serialize)]
+struct EmbedFooter<'a> {
+    text: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    icon_url: Option<Cow<'a, str>>,
+}
+
+impl EmbedFooter<'_> {
+    fn is_unset(&self) -> bool {
+        self.text.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+struct EmbedAuthor<'a> {
+    name: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    icon_url: Option<Cow<'a, str>>,
+}
+
+impl EmbedAuthor<'_> {
+    fn is_unset(&self) -> bool {
+        self.name.is_none()
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
+struct EmbedMedia<'a> {
+    url: Cow<'a, str>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct EmbedField<'a> {
+    name: Cow<'a, str>,
+    value: Cow<'a, str>,
+    #[serde(skip_serializing_if = "is_false")]
+    inline: bool,
+}
+
+/// An embed to send, built with `Embed::new().title(..).field(..)`.
+///
+/// Images can be files uploaded with the message, using
+/// `NewAttachment::attachment_url` as their url.
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct Embed<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
     title: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     description: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    color: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp: Option<Timestamp>,
+    #[serde(skip_serializing_if = "EmbedFooter::is_unset")]
+    footer: EmbedFooter<'a>,
+    #[serde(skip_serializing_if = "EmbedAuthor::is_unset")]
+    author: EmbedAuthor<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    image: Option<EmbedMedia<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thumbnail: Option<EmbedMedia<'a>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    fields: Vec<EmbedField<'a>>,
 }
 
 impl<'a> Embed<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    pub fn title<T: Into<Cow<'a, str>>>(mut self, title: T) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+    
+    pub fn description<T: Into<Cow<'a, str>>>(mut self, description: T) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+    
+    /// Where the title links to.
+    pub fn url<T: Into<Cow<'a, str>>>(mut self, url: T) -> Self {
+        self.url = Some(url.into());
+        self
+    }
+    
+    /// The color of the embed's left edge as `0xRRGGBB`.
+    pub fn color(mut self, color: u32) -> Self {
+        self.color = Some(color);
+        self
+    }
+    
+    pub fn timestamp(mut self, timestamp: Timestamp) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+    
+    pub fn footer<T: Into<Cow<'a, str>>>(mut self, text: T) -> Self {
+        self.footer.text = Some(text.into());
+        self
+    }
+    
+    /// Only sent with a `footer`.
+    pub fn footer_icon<T: Into<Cow<'a, str>>>(mut self, url: T) -> Self {
+        self.footer.icon_url = Some(url.into());
+        self
+    }
+    
+    pub fn author<T: Into<Cow<'a, str>>>(mut self, name: T) -> Self {
+        self.author.name = Some(name.into());
+        self
+    }
+    
+    /// Where the author's name links to, only sent with an `author`.
+    pub fn author_url<T: Into<Cow<'a, str>>>(mut self, url: T) -> Self {
+        self.author.url = Some(url.into());
+        self
+    }
+    
+    /// Only sent with an `author`.
+    pub fn author_icon<T: Into<Cow<'a, str>>>(mut self, url: T) -> Self {
+        self.author.icon_url = Some(url.into());
+        self
+    }
+    
+    pub fn image<T: Into<Cow<'a, str>>>(mut self, url: T) -> Self {
+        self.image = Some(EmbedMedia { url: url.into() });
+        self
+    }
+    
+    pub fn thumbnail<T: Into<Cow<'a, str>>>(mut self, url: T) -> Self {
+        self.thumbnail = Some(EmbedMedia { url: url.into() });
+        self
+    }
+    
+    /// Adds a field, `inline` fields are shown next to each other.
+    pub fn field<N, V>(mut self, name: N, value: V, inline: bool) -> Self
+        where
+            N: Into<Cow<'a, str>>,
+            V: Into<Cow<'a, str>>,
+    {
+        self.fields.push(EmbedField {
+            name: name.into(),
+            value: value.into(),
+            inline: inline,
+        });
+        self
+    }
+    
+    /// Checks the embed against discord's limits, returning the total length
+    /// of its text. `path` is where the embed is in the message.
+    fn check(&self, path: &str) -> Result<usize, EmbedError> {
+        fn check_len(path: &str, field: &str, text: &str, max: usize) -> Result<usize, EmbedError> {
+            let length = text.chars().count();
+            if length > max {
+                return Err(EmbedError::TooLong {
+                    field: format!("{}.{}", path, field),
+                    length: length,
+                    max: max,
+                })
+            }
+            Ok(length)
+        }
+        
+        fn check_required(path: &str, field: &str, text: &str, max: usize) -> Result<usize, EmbedError> {
+            if text.trim().is_empty() {
+                return Err(EmbedError::Missing(format!("{}.{}", path, field)))
+            }
+            check_len(path, field, text, max)
+        }
+        
+        let nothing_set = self.title.is_none() && self.description.is_none()
+            && self.url.is_none() && self.color.is_none() && self.timestamp.is_none()
+            && self.fields.is_empty() && self.image.is_none() && self.thumbnail.is_none()
+            && self.footer.is_unset() && self.author.is_unset();
+        if nothing_set {
+            return Err(EmbedError::Empty(path.to_string()))
+        }
+        
+        if self.fields.len() > EMBED_FIELDS_LIMIT {
+            return Err(EmbedError::TooManyFields(self.fields.len()))
+        }
+        
+        let mut total = 0;
+        if let Some(title) = &self.title {
+            total += check_len(path, "title", title, EMBED_TITLE_LIMIT)?;
+        }
+        if let Some(description) = &self.description {
+            total += check_len(path, "description", description, EMBED_DESCRIPTION_LIMIT)?;
+        }
+        if let Some(text) = &self.footer.text {
+            total += check_required(path, "footer.text", text, EMBED_FOOTER_LIMIT)?;
+        }
+        if let Some(name) = &self.author.name {
+            total += check_required(path, "author.name", name, EMBED_AUTHOR_LIMIT)?;
+        }
+        for (n, field) in self.fields.iter().enumerate() {
+            total += check_required(path, &format!("fields[{}].name", n), &field.name, EMBED_FIELD_NAME_LIMIT)?;
+            total += check_required(path, &format!("fields[{}].value", n), &field.value, EMBED_FIELD_VALUE_LIMIT)?;
+        }
+        
+        Ok(total)
+    }
+    
+    /// Checks the embed against discord's limits for a single embed.
+    pub fn validate(&self) -> Result<(), EmbedError> {
+        let total = self.check("embed")?;
+        if total > EMBED_TOTAL_LIMIT {
+            return Err(EmbedError::TotalTooLong(total))
+        }
+        Ok(())
+    }
+    
     fn into_owned(self) -> Embed<'static> {
         Embed {
-            title: self.title.map(own_cow),
-            description: self.description.map(own_cow),
+            title: own_opt_cow(self.title),
+            description: own_opt_cow(self.description),
+            url: own_opt_cow(self.url),
+            color: self.color,
+            timestamp: self.timestamp,
+            footer: EmbedFooter {
+                text: own_opt_cow(self.footer.text),
+                icon_url: own_opt_cow(self.footer.icon_url),
+            },
+            author: EmbedAuthor {
+                name: own_opt_cow(self.author.name),
+                url: own_opt_cow(self.author.url),
+                icon_url: own_opt_cow(self.author.icon_url),
+            },
+            image: self.image.map(|image| EmbedMedia { url: own_cow(image.url) }),
+            thumbnail: self.thumbnail.map(|image| EmbedMedia { url: own_cow(image.url) }),
+            fields: self.fields.into_iter().map(|field| EmbedField {
+                name: own_cow(field.name),
+                value: own_cow(field.value),
+                inline: field.inline,
+            }).collect(),
         }
     }
 }
 
+/// Checks the embeds of one message against discord's limits, which also
+/// limit the length of all of them together.
+pub fn validate_embeds(embeds: &[Embed<'_>]) -> Result<(), EmbedError> {
+    if embeds.len() > EMBEDS_PER_MESSAGE_LIMIT {
+        return Err(EmbedError::TooManyEmbeds(embeds.len()))
+    }
+    
+    let mut total = 0;
+    for (n, embed) in embeds.iter().enumerate() {
+        total += embed.check(&format!("embeds[{}]", n))?;
+    }
+    if total > EMBED_TOTAL_LIMIT {
+        return Err(EmbedError::TotalTooLong(total))
+    }
+    
+    Ok(())
+}
+
 #[allow(dead_code)]
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "lowercase")]
@@ -32,8 +369,123 @@ enum MentionTypes {
 #[derive(Debug, Clone, Serialize)]
 struct AllowedMentions {
     parse: Vec<MentionTypes>,
-    users: Option<Vec<Snowflake>>,
-    roles: Option<Vec<Snowflake>>,
+    users: Option<Vec<UserId>>,
+    roles: Option<Vec<RoleId>>,
+}
+
+// `Bytes` so the body can be rebuilt for every attempt without copying
+#[derive(Debug, Clone)]
+enum AttachmentData {
+    Bytes(Bytes),
+    Path(PathBuf),
+}
+
+/// A file to upload with a message.
+#[derive(Debug, Clone)]
+pub struct NewAttachment<'a> {
+    filename: Cow<'a, str>,
+    description: Option<Cow<'a, str>>,
+    spoiler: bool,
+    data: AttachmentData,
+}
+
+impl<'a> NewAttachment<'a> {
+    pub fn from_bytes<N, B>(filename: N, data: B) -> Self
+        where
+            N: Into<Cow<'a, str>>,
+            B: Into<Bytes>,
+    {
+        NewAttachment {
+            filename: filename.into(),
+            description: None,
+            spoiler: false,
+            data: AttachmentData::Bytes(data.into()),
+        }
+    }
+    
+    /// A file read when the message is sent, named after the last component
+    /// of `path`.
+    pub fn from_path<P: Into<PathBuf>>(path: P) -> Self {
+        let path = path.into();
+        let filename = path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        
+        NewAttachment {
+            filename: filename.into(),
+            description: None,
+            spoiler: false,
+            data: AttachmentData::Path(path),
+        }
+    }
+    
+    pub fn filename<T: Into<Cow<'a, str>>>(mut self, filename: T) -> Self {
+        self.filename = filename.into();
+        self
+    }
+    
+    /// Alt text for the file.
+    pub fn description<T: Into<Cow<'a, str>>>(mut self, description: T) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+    
+    /// Hides the file behind a spoiler, by prefixing its name with `SPOILER_`.
+    pub fn spoiler(mut self, spoiler: bool) -> Self {
+        self.spoiler = spoiler;
+        self
+    }
+    
+    /// The name discord will show for the file.
+    pub fn uploaded_filename(&self) -> Cow<'_, str> {
+        if self.spoiler && !self.filename.starts_with("SPOILER_") {
+            Cow::Owned(format!("SPOILER_{}", self.filename))
+        } else {
+            Cow::Borrowed(&*self.filename)
+        }
+    }
+    
+    /// The url to use for the file in the message's embeds, e.g. as an image.
+    pub fn attachment_url(&self) -> String {
+        format!("attachment://{}", self.uploaded_filename())
+    }
+    
+    async fn load(&self) -> Result<Bytes, Error> {
+        match &self.data {
+            AttachmentData::Bytes(data) => Ok(data.clone()),
+            AttachmentData::Path(path) => tokio::fs::read(path).await
+                .map(Bytes::from)
+                .map_err(|err| Error::Other(format!("Could not read attachment {}: {}", path.display(), err))),
+        }
+    }
+    
+    fn into_owned(self) -> NewAttachment<'static> {
+        NewAttachment {
+            filename: own_cow(self.filename),
+            description: self.description.map(own_cow),
+            spoiler: self.spoiler,
+            data: self.data,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct AttachmentMetadata<'a> {
+    id: usize,
+    filename: Cow<'a, str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<&'a str>,
+}
+
+// the `attachments` of `payload_json`, the ids match the `files[n]` parts
+fn ser_attachments<S>(attachments: &[NewAttachment<'_>], serializer: S) -> Result<S::Ok, S::Error>
+    where S: serde::Serializer,
+{
+    serializer.collect_seq(attachments.iter().enumerate().map(|(id, file)| AttachmentMetadata {
+        id: id,
+        filename: file.uploaded_filename(),
+        description: file.description.as_deref(),
+    }))
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -41,6 +493,10 @@ pub struct NewMessage<'a> {
     content: Option<Cow<'a, str>>,
     embeds: Option<Vec<Embed<'a>>>,
     allowed_mentions: Option<AllowedMentions>,
+    #[serde(skip_serializing_if = "Vec::is_empty", serialize_with = "ser_attachments")]
+    attachments: Vec<NewAttachment<'a>>,
+    #[serde(skip)]
+    retry: Option<RetryPolicy>,
 }
 
 impl<'a> NewMessage<'a> {
@@ -49,6 +505,8 @@ impl<'a> NewMessage<'a> {
             content: Some(msg.into()),
             embeds: None,
             allowed_mentions: None,
+            attachments: Vec::new(),
+            retry: None,
         }
     }
     
@@ -60,6 +518,19 @@ impl<'a> NewMessage<'a> {
         });
     }
     
+    /// Uploads `file` with the message, when the content is split into
+    /// several messages the files are sent with the last one.
+    pub fn attach(&mut self, file: NewAttachment<'a>) {
+        self.attachments.push(file);
+    }
+    
+    /// Retries sending this message as `policy` says instead of the client's
+    /// policy, e.g. `RetryPolicy::new().retry_posts(true)` for a message
+    /// that may safely end up being sent twice.
+    pub fn retry_policy(&mut self, policy: RetryPolicy) {
+        self.retry = Some(policy);
+    }
+    
     pub fn embed_desc<T1, T2>(title: Option<T1>, msg: T2) -> Self
     // pub fn embed_desc<T>(title: Option<T>, msg: T) -> Self
         where 
@@ -76,22 +547,46 @@ impl<'a> NewMessage<'a> {
                 // title: None,
                 // description: Some(msg.into()),
                 description: Some(temp),
+                ..Embed::default()
             }]),
             allowed_mentions: None,
+            attachments: Vec::new(),
+            retry: None,
         }
     }
     
     pub fn embed_temp(msg: String) -> Self {
         NewMessage {
             content: None,
-            embeds: Some(vec![Embed {
-                title: None,
-                description: Some(msg.into()),
-            }]),
+            embeds: Some(vec![Embed::new().description(msg)]),
+            allowed_mentions: None,
+            attachments: Vec::new(),
+            retry: None,
+        }
+    }
+    
+    /// A message of only `embeds`, see `add_embed` to add them to one with
+    /// content.
+    pub fn with_embeds(embeds: Vec<Embed<'a>>) -> Self {
+        NewMessage {
+            content: None,
+            embeds: Some(embeds),
             allowed_mentions: None,
+            attachments: Vec::new(),
+            retry: None,
         }
     }
     
+    pub fn add_embed(&mut self, embed: Embed<'a>) {
+        self.embeds.get_or_insert_with(Vec::new).push(embed);
+    }
+    
+    /// Checks the message's embeds against discord's limits, `send` does
+    /// this before sending anything.
+    pub fn validate(&self) -> Result<(), EmbedError> {
+        validate_embeds(self.embeds.as_deref().unwrap_or_default())
+    }
+    
     pub fn into_owned(self) -> NewMessage<'static> {
         // let content = Cow::Owned(self.content.map(|x| x.into_owned()).unwrap_or("".into()));
         // let content = self.content.map(own_cow);
@@ -100,10 +595,79 @@ impl<'a> NewMessage<'a> {
             // embeds: self.embeds.map(|embed| embed.to_owned()),
             embeds: self.embeds.map(|embed| embed.into_iter().map(|x| x.into_owned()).collect()),
             allowed_mentions: self.allowed_mentions,
+            attachments: self.attachments.into_iter().map(|file| file.into_owned()).collect(),
+            retry: self.retry,
         }
     }
 }
 
+#[derive(Debug, Clone, Serialize)]
+struct KeepAttachment {
+    id: Snowflake,
+}
+
+/// Changes to an existing message, fields that aren't set stay as they are.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EditMessage<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<Option<Cow<'a, str>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    embeds: Option<Vec<Embed<'a>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    allowed_mentions: Option<AllowedMentions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    components: Option<Vec<Component>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    attachments: Option<Vec<KeepAttachment>>,
+}
+
+impl<'a> EditMessage<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    pub fn content<T: Into<Cow<'a, str>>>(mut self, content: T) -> Self {
+        self.content = Some(Some(content.into()));
+        self
+    }
+    
+    pub fn remove_content(mut self) -> Self {
+        self.content = Some(None);
+        self
+    }
+    
+    /// Replaces all embeds, an empty list removes them.
+    pub fn embeds(mut self, embeds: Vec<Embed<'a>>) -> Self {
+        self.embeds = Some(embeds);
+        self
+    }
+    
+    pub fn suppress_mentions(mut self) -> Self {
+        self.allowed_mentions = Some(AllowedMentions {
+            parse: Vec::new(),
+            users: None,
+            roles: None,
+        });
+        self
+    }
+    
+    /// Replaces all components, an empty list removes them.
+    pub fn components(mut self, components: Vec<Component>) -> Self {
+        self.components = Some(components);
+        self
+    }
+    
+    pub fn validate(&self) -> Result<(), EmbedError> {
+        validate_embeds(self.embeds.as_deref().unwrap_or_default())
+    }
+    
+    /// Removes every attachment not in `ids`.
+    pub fn keep_attachments<I: IntoIterator<Item = Snowflake>>(mut self, ids: I) -> Self {
+        self.attachments = Some(ids.into_iter().map(|id| KeepAttachment { id: id }).collect());
+        self
+    }
+}
+
 impl From<&str> for NewMessage<'_> {
     fn from(x: &str) -> Self {
         NewMessage::new(x.to_string())
@@ -121,12 +685,18 @@ impl From<String> for NewMessage<'static> {
 // use tokio::time::delay_for;
 // use hyper_tls;
 // use hyper_tls::HttpsConnector;
-use crate::discord::Snowflake;
+use std::ops::Deref;
+use std::sync::{Arc, RwLock};
+
+use crate::discord::{is_false, Snowflake, Timestamp, ChannelId, MessageId, UserId, RoleId, Message, Component, ApiError, ErrorCode};
+use crate::ratelimit::{RateLimiter, RateLimitBackend, Route, BucketInfo};
+use crate::retry::RetryPolicy;
 // use hyper;
 // use hyper::Body;
 // use hyper::Request;
 // use hyper::client::Client;
 use reqwest::Client;
+use reqwest::multipart::{Form, Part};
 use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
 use reqwest::StatusCode;
 use serde_json;
@@ -142,6 +712,8 @@ pub enum Error {
         msg: String,
         status_code: StatusCode,
         body: String,
+        /// `None` when the body isn't discord's JSON error, e.g. from a proxy.
+        error: Option<ApiError>,
     },
     // TransportError(hyper::Error),
     // HttpError(hyper::http::Error),
@@ -150,6 +722,10 @@ pub enum Error {
     RateLimited {
         retry_after: u64,
     },
+    /// Caught before sending, discord would have rejected the request.
+    InvalidEmbed(EmbedError),
+    /// Caught before sending, discord would have rejected the request.
+    InvalidBulkDelete(BulkDeleteError),
     Other(String)
 }
 
@@ -163,11 +739,67 @@ pub enum Error {
 //         Error::HttpError(err)
 //     }
 // }
+impl Error {
+    /// The error discord returned, if the request got a JSON error response.
+    pub fn api_error(&self) -> Option<&ApiError> {
+        match self {
+            Error::DiscordError { error, .. } => error.as_ref(),
+            _ => None,
+        }
+    }
+    
+    pub fn code(&self) -> Option<ErrorCode> {
+        self.api_error().map(|err| err.code)
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::DiscordError { status_code, error: Some(_), .. } => {
+                write!(f, "discord returned {}", status_code)
+            }
+            Error::DiscordError { status_code, body, .. } => {
+                write!(f, "discord returned {}: {}", status_code, body)
+            }
+            Error::ReqwestError(_) => write!(f, "http request failed"),
+            Error::DecodeError(_) => write!(f, "response was not valid UTF-8"),
+            Error::RateLimited { retry_after } => write!(f, "rate limited, retry after {}s", retry_after),
+            Error::InvalidEmbed(err) => write!(f, "invalid embed: {}", err),
+            Error::InvalidBulkDelete(err) => write!(f, "invalid bulk delete: {}", err),
+            Error::Other(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::DiscordError { error: Some(err), .. } => Some(err),
+            Error::ReqwestError(err) => Some(err),
+            Error::DecodeError(err) => Some(err),
+            Error::InvalidEmbed(err) => Some(err),
+            Error::InvalidBulkDelete(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
 impl From<reqwest::Error> for Error {
     fn from(err: reqwest::Error) -> Self {
         Error::ReqwestError(err)
     }
 }
+impl From<EmbedError> for Error {
+    fn from(err: EmbedError) -> Self {
+        Error::InvalidEmbed(err)
+    }
+}
+impl From<BulkDeleteError> for Error {
+    fn from(err: BulkDeleteError) -> Self {
+        Error::InvalidBulkDelete(err)
+    }
+}
 impl From<std::string::FromUtf8Error> for Error {
     fn from(err: std::string::FromUtf8Error) -> Self {
         Error::DecodeError(err)
@@ -184,13 +816,45 @@ impl From<std::string::FromUtf8Error> for Error {
 // pub type Https = hyper_rustls::HttpsConnector<hyper::client::HttpConnector>;
 // pub type Https = hyper_rustls::HttpsConnector<hyper::client::HttpConnector>;
 // pub type TheClient = Client<Https, Body>;
-pub type TheClient = Client;
+
+/// The http client shared by `Discord` and its `SendHandle`s, clones share
+/// the same connection pool and rate limits.
+#[derive(Debug, Clone)]
+pub struct TheClient {
+    http: Client,
+    ratelimit: Arc<dyn RateLimitBackend>,
+    retry: Arc<RwLock<RetryPolicy>>,
+}
+
+impl TheClient {
+    /// Changes the global requests per second for every clone of this client.
+    pub async fn set_global_rate_limit(&self, per_second: u32) {
+        self.ratelimit.set_global_limit(per_second).await
+    }
+    
+    /// Changes how failed requests are retried for every clone of this client.
+    pub fn set_retry_policy(&self, policy: RetryPolicy) {
+        *self.retry.write().unwrap() = policy;
+    }
+    
+    pub fn retry_policy(&self) -> RetryPolicy {
+        *self.retry.read().unwrap()
+    }
+}
+
+impl Deref for TheClient {
+    type Target = Client;
+    
+    fn deref(&self) -> &Client {
+        &self.http
+    }
+}
 
 // pub fn get_https() -> Result<Https, Error> {
 //     // Ok(HttpsConnector::new())
 //         // .map_err(|err| err.into())
 //     // Ok(hyper_rustls::HttpsConnector::new())
-    
+
 //     let https = hyper_rustls::HttpsConnectorBuilder::new()
 //         // .with_native_roots()
 //         .with_webpkiroots()
@@ -201,16 +865,27 @@ pub type TheClient = Client;
 //     Ok(https)
 // }
 
-pub fn get_client() -> Result<TheClient, Error> {
+pub fn get_client() -> Result<TheClient, crate::Error> {
+    get_client_with_rate_limiter(Arc::new(RateLimiter::new()))
+}
+
+/// Like `get_client`, but keeps rate limits in `backend`. Every clone of the
+/// client shares it, so pick the backend here rather than after cloning.
+pub fn get_client_with_rate_limiter(backend: Arc<dyn RateLimitBackend>) -> Result<TheClient, crate::Error> {
     // let https = get_https()?;
     
     // let client = Client::builder()
     //     .build(https);
     let client = reqwest::ClientBuilder::new()
         .timeout(std::time::Duration::from_secs(10))
-        .build()?;
+        .build()
+        .map_err(Error::from)?;
     
-    Ok(client)
+    Ok(TheClient {
+        http: client,
+        ratelimit: backend,
+        retry: Arc::new(RwLock::new(RetryPolicy::default())),
+    })
 }
 
 #[derive(Debug, Deserialize)]
@@ -219,59 +894,111 @@ pub struct DiscordRateLimitResponse {
     global: bool,
 }
 
-pub(crate) async fn send_retry_rate_limit<'a, ReqF>(client: &'a TheClient, req_builder: ReqF) -> Result<String, Error>
+pub(crate) async fn send_retry_rate_limit<'a, ReqF>(client: &'a TheClient, route: &'a Route, req_builder: ReqF) -> Result<String, Error>
     where
         // C: hyper::client::connect::Connect + 'static + Clone + Send + Sync,
         ReqF: Fn() -> Result<reqwest::Request, Error> + 'a,
 {
+    let retry = client.retry_policy();
+    send_retry_policy(client, route, &retry, req_builder).await
+}
+
+/// `send_retry_rate_limit` retrying failures as `retry` says instead of the
+/// client's policy.
+pub(crate) async fn send_retry_policy<'a, ReqF>(client: &'a TheClient, route: &'a Route, retry: &'a RetryPolicy, req_builder: ReqF) -> Result<String, Error>
+    where
+        ReqF: Fn() -> Result<reqwest::Request, Error> + 'a,
+{
+    let mut attempt = 0;
+    
     loop {
         let req = req_builder()?;
+        let method = req.method().clone();
         
-        let res: reqwest::Response = client.execute(req).await?;
+        client.ratelimit.acquire(route).await;
+        
+        let res: reqwest::Response = match client.execute(req).await {
+            Ok(res) => res,
+            Err(err) => {
+                client.ratelimit.failed(route).await;
+                match retry.delay(&method, attempt) {
+                    Some(delay) => {
+                        attempt += 1;
+                        tokio::time::sleep(delay).await;
+                        continue
+                    }
+                    None => return Err(err.into()),
+                }
+            }
+        };
+        
+        client.ratelimit.update(route, BucketInfo::from_headers(res.headers())).await;
         
         // let (parts, body) = res.into_parts();
         
         // let body = {
         //     // let b: Vec<u8> = hyper::body::to_bytes(body).await?.to_vec();
         //     let b: Vec<u8> = res.bytes().await?.to_vec();
-            
+        
         //     String::from_utf8(b)?
         // };
         let status = res.status();
         
-        let b = res.bytes().await?.to_vec();
+        let b = match res.bytes().await {
+            Ok(b) => b.to_vec(),
+            Err(err) => match retry.delay(&method, attempt) {
+                Some(delay) => {
+                    attempt += 1;
+                    tokio::time::sleep(delay).await;
+                    continue
+                }
+                None => return Err(err.into()),
+            },
+        };
         let body = String::from_utf8(b)?;
         
         if status == StatusCode::TOO_MANY_REQUESTS {
             let res: DiscordRateLimitResponse = serde_json::from_str(&body)
                 .map_err(|err| Error::Other(format!("Malformed rate limit message {}", err)))?;
             
+            let retry_after = ::std::time::Duration::from_secs_f64(res.retry_after);
+            
             if res.global {
-                dbg!(&res);
+                client.ratelimit.global_limited(retry_after).await;
+            } else {
+                client.ratelimit.limited(route, retry_after).await;
             }
             
             // delay_for(::std::time::Duration::from_millis(res.retry_after)).await;
             // delay_for(::std::time::Duration::from_secs_f64(res.retry_after)).await;
-            tokio::time::sleep(::std::time::Duration::from_secs_f64(res.retry_after)).await;
+            tokio::time::sleep(retry_after).await;
             continue
         }
         
+        if status.is_server_error() {
+            if let Some(delay) = retry.delay(&method, attempt) {
+                attempt += 1;
+                tokio::time::sleep(delay).await;
+                continue
+            }
+        }
+        
         if !status.is_success() {
             return Err(Error::DiscordError {
                 msg: format!("Status Code {:?}", status),
                 status_code: status,
-                // body: body,
-                body: "".into(),
+                error: serde_json::from_str(&body).ok(),
+                body: body,
             });
         }
-        
-        
-        
+
+
+
         break Ok(body)
     }
 }
 
-fn split_msg(msg: &str) -> Vec<Cow<str>> {
+fn split_msg(msg: &str) -> Vec<Cow<'_, str>> {
     if msg.len() > 2000 {
         let mut new_out = vec![Cow::Owned(String::new())];
         let split = msg.lines();
@@ -321,17 +1048,40 @@ fn split_msg(msg: &str) -> Vec<Cow<str>> {
     }
 }
 
-async fn send_inner<'a>(msg: &'a NewMessage<'a>, auth: &str, url: &str, client: &'a TheClient) -> Result<(), Error>
+async fn send_inner<'a>(msg: &'a NewMessage<'a>, auth: &str, url: &str, route: &Route, client: &'a TheClient) -> Result<Message, Error>
         // where
         //     C: hyper::client::connect::Connect + 'static + Clone + Send + Sync,
     {
     
-    let _body = send_retry_rate_limit(client, || {
+    let mut files = Vec::with_capacity(msg.attachments.len());
+    for file in &msg.attachments {
+        files.push(file.load().await?);
+    }
+    
+    let retry = msg.retry.unwrap_or_else(|| client.retry_policy());
+    
+    let body = send_retry_policy(client, route, &retry, || {
         let body: String = serde_json::to_string(&msg)
             .map_err(|err| Error::Other(format!("Could not serialize message {}", err)))?
             // .into();
             ;
         
+        if !files.is_empty() {
+            // the form's body is a stream, so it is built again for every attempt
+            let mut form = Form::new()
+                .part("payload_json", Part::text(body).mime_str("application/json")?);
+            for (n, (file, data)) in msg.attachments.iter().zip(&files).enumerate() {
+                form = form.part(format!("files[{}]", n),
+                    Part::stream(data.clone()).file_name(file.uploaded_filename().into_owned()));
+            }
+            
+            return client.post(url)
+                .header(AUTHORIZATION, auth)
+                .multipart(form)
+                .build()
+                .map_err(|err| err.into())
+        }
+        
         // Request::builder()
         client.post(url)
             // .method("POST")
@@ -345,27 +1095,34 @@ async fn send_inner<'a>(msg: &'a NewMessage<'a>, auth: &str, url: &str, client:
             .map_err(|err| err.into())
     }).await?;
     
-    Ok(())
+    let message: Message = serde_json::from_str(&body)
+        .map_err(|err| Error::Other(format!("Malformed sent message {}", err)))?;
+    
+    Ok(message)
 }
 
-pub async fn send<'a>(to: Snowflake, msg: &'a NewMessage<'a>, base_url: &'a str, token: &'a str, client: &'a TheClient) -> Result<(), Error>
+pub async fn send<'a>(to: ChannelId, msg: &'a NewMessage<'a>, base_url: &'a str, token: &'a str, client: &'a TheClient) -> Result<Vec<Message>, crate::Error>
     // where
     //     C: hyper::client::connect::Connect + 'static + Clone + Send + Sync,
 {
     // https://discordapp.com/api/v6/gateway/bot
     
-    let Snowflake(to_id) = to;
+    msg.validate().map_err(Error::from)?;
     
-    let url = &format!("{}/channels/{}/messages", base_url, to_id);
+    let url = &format!("{}/channels/{}/messages", base_url, to);
     
     let auth = &format!("Bot {}", token);
+    let route = Route::new("POST /channels/{channel}/messages", to);
     
     // let mut msgs = Vec::new();
+    let mut sent = Vec::new();
     
     let full_msg = msg;
     match (&msg.content, &msg.embeds) {
         (Some(content), None) => {
-            for msg in split_msg(content) {
+            let parts = split_msg(content);
+            let last = parts.len() - 1;
+            for (n, msg) in parts.into_iter().enumerate() {
                 // let ref msg = NewMessageBorrowed {
                 //     content: &*msg,
                 // };
@@ -374,20 +1131,145 @@ pub async fn send<'a>(to: Snowflake, msg: &'a NewMessage<'a>, base_url: &'a str,
                     content: msg.into(),
                     embeds: None,
                     allowed_mentions: full_msg.allowed_mentions.clone(),
+                    attachments: if n == last { full_msg.attachments.clone() } else { Vec::new() },
+                    retry: full_msg.retry,
                 };
                 
-                send_inner(&msg, auth, url, client).await?;
+                sent.push(send_inner(&msg, auth, url, &route, client).await?);
             }
         }
         _ => {
-            send_inner(msg, auth, url, client).await?;
+            sent.push(send_inner(msg, auth, url, &route, client).await?);
         }
     }
     
     // for msg in msgs {
-    //     send_inner(&msg, auth, url, client).await?;
+    //     send_inner(&msg, auth, url, &route, client).await?;
     // }
     
-    Ok(())
+    Ok(sent)
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn attachments_in_payload_json() {
+        let mut msg = NewMessage::from("look");
+        msg.attach(NewAttachment::from_bytes("cat.png", &b"png"[..]).description("a cat"));
+        msg.attach(NewAttachment::from_path("/tmp/plot.svg").spoiler(true));
+        
+        assert_eq!(msg.attachments[1].attachment_url(), "attachment://SPOILER_plot.svg");
+        assert_eq!(serde_json::to_value(&msg).unwrap(), serde_json::json!({
+            "content": "look",
+            "embeds": null,
+            "allowed_mentions": null,
+            "attachments": [
+                {"id": 0, "filename": "cat.png", "description": "a cat"},
+                {"id": 1, "filename": "SPOILER_plot.svg"},
+            ],
+        }));
+    }
+    
+    #[test]
+    fn embed_builder() {
+        let embed = Embed::new()
+            .title("Title")
+            .color(0x00ff00)
+            .footer_icon("https://example.com/icon.png")
+            .footer("Footer")
+            .author("Author")
+            .image("attachment://cat.png")
+            .field("a", "1", true)
+            .field("b", "2", false);
+        
+        assert_eq!(serde_json::to_value(&embed).unwrap(), serde_json::json!({
+            "title": "Title",
+            "color": 0x00ff00,
+            "footer": {"text": "Footer", "icon_url": "https://example.com/icon.png"},
+            "author": {"name": "Author"},
+            "image": {"url": "attachment://cat.png"},
+            "fields": [
+                {"name": "a", "value": "1", "inline": true},
+                {"name": "b", "value": "2"},
+            ],
+        }));
+    }
+    
+    #[test]
+    fn embed_limits() {
+        let long = "x".repeat(EMBED_FIELD_VALUE_LIMIT + 1);
+        let embed = Embed::new().field("ok", "fine", false).field("long", &*long, false);
+        assert_eq!(validate_embeds(&[Embed::new().title("ok"), embed]), Err(EmbedError::TooLong {
+            field: "embeds[1].fields[1].value".into(),
+            length: EMBED_FIELD_VALUE_LIMIT + 1,
+            max: EMBED_FIELD_VALUE_LIMIT,
+        }));
+        
+        let fields = (0..26).fold(Embed::new(), |embed, _| embed.field("f", "v", false));
+        assert_eq!(fields.validate(), Err(EmbedError::TooManyFields(26)));
+        
+        // each is within its own limits but not together
+        let description = "x".repeat(EMBED_DESCRIPTION_LIMIT);
+        let embeds = vec![Embed::new().description(&*description); 2];
+        assert!(embeds[0].validate().is_ok());
+        assert_eq!(NewMessage::with_embeds(embeds).validate(), Err(EmbedError::TotalTooLong(2 * EMBED_DESCRIPTION_LIMIT)));
+    }
+    
+    #[test]
+    fn edit_message_payload() {
+        assert_eq!(serde_json::to_value(EditMessage::new()).unwrap(), serde_json::json!({}));
+        
+        let edit = EditMessage::new()
+            .remove_content()
+            .keep_attachments([Snowflake(1), Snowflake(2)]);
+        assert_eq!(serde_json::to_value(&edit).unwrap(), serde_json::json!({
+            "content": null,
+            "attachments": [{"id": "1"}, {"id": "2"}],
+        }));
+        
+        let edit = EditMessage::new()
+            .content("edited")
+            .embeds(Vec::new())
+            .keep_attachments([]);
+        assert_eq!(serde_json::to_value(&edit).unwrap(), serde_json::json!({
+            "content": "edited",
+            "embeds": [],
+            "attachments": [],
+        }));
+    }
+    
+    #[test]
+    fn embed_required_text() {
+        assert_eq!(Embed::new().validate(), Err(EmbedError::Empty("embed".into())));
+        assert_eq!(NewMessage::with_embeds(vec![Embed::new().title("a"), Embed::new()]).validate(),
+            Err(EmbedError::Empty("embeds[1]".into())));
+        // discord takes embeds that only have a color or an image
+        assert!(Embed::new().color(0x00ff00).validate().is_ok());
+        assert!(Embed::new().thumbnail("attachment://cat.png").validate().is_ok());
+        
+        // icons and urls wait for the text they belong to
+        let icon = "https://example.com/icon.png";
+        assert_eq!(Embed::new().footer_icon(icon).validate(), Err(EmbedError::Empty("embed".into())));
+        assert_eq!(Embed::new().author_url(icon).author_icon(icon).validate(), Err(EmbedError::Empty("embed".into())));
+        assert_eq!(serde_json::to_value(Embed::new().title("a").author_icon(icon)).unwrap(), serde_json::json!({"title": "a"}));
+        assert!(Embed::new().footer_icon(icon).footer("Footer").validate().is_ok());
+        assert!(Embed::new().author_icon(icon).author("Author").validate().is_ok());
+        assert_eq!(Embed::new().footer(" ").validate(), Err(EmbedError::Missing("embed.footer.text".into())));
+        assert_eq!(Embed::new().author("").validate(), Err(EmbedError::Missing("embed.author.name".into())));
+        
+        assert_eq!(Embed::new().field("", "v", false).validate(), Err(EmbedError::Missing("embed.fields[0].name".into())));
+        assert_eq!(Embed::new().field("n", " ", false).validate(), Err(EmbedError::Missing("embed.fields[0].value".into())));
+    }
+    
+    #[test]
+    fn retry_policy_is_shared() {
+        let client = get_client().unwrap();
+        let handle = client.clone();
+        
+        client.set_retry_policy(RetryPolicy::none());
+        assert_eq!(handle.retry_policy(), RetryPolicy::none());
+    }
+}
@@ -1,34 +1,32 @@
+#![allow(clippy::needless_lifetimes)]
 
 // use hyper::{ Client, Request, Body };
 use reqwest::header::{ AUTHORIZATION, CONTENT_TYPE, CONTENT_LENGTH };
-use percent_encoding::{ percent_encode, NON_ALPHANUMERIC };
 
-use crate::discord::Snowflake;
+use crate::discord::{ChannelId, MessageId, ReactionType};
 
 use crate::send_message::Error;
-use crate::send_message::send_retry_rate_limit;
+use crate::send_message::{send_retry_rate_limit, TheClient};
+use crate::ratelimit::Route;
 
 pub async fn set_reaction<'a>(
-    channel: Snowflake,
-    msg: Snowflake,
-    emoji: &str,
+    channel: ChannelId,
+    msg: MessageId,
+    emoji: &ReactionType,
     base_url: &str,
     auth: &str,
     // client: &'a Client<C, Body>,
-    client: &'a reqwest::Client,
+    client: &'a TheClient,
     ) -> Result<(), Error>
     // where
     //     C: hyper::client::connect::Connect + 'static + Clone + Send + Sync,
 {
-    let Snowflake(channel_id) = channel;
-    let Snowflake(msg_id) = msg;
-    
-    let emoji: String = percent_encode(emoji.as_bytes(), NON_ALPHANUMERIC).collect();
-    
     let url = &format!("{}/channels/{}/messages/{}/reactions/{}/@me",
-        base_url, channel_id, msg_id, emoji);
+        base_url, channel, msg, emoji.url_segment());
+    
+    let route = Route::new("PUT /channels/{channel}/messages/{message}/reactions/{emoji}/@me", channel);
     
-    send_retry_rate_limit(client, || {
+    send_retry_rate_limit(client, &route, || {
         // Request::builder()
         client.put(url)
             // .method("PUT")
@@ -0,0 +1,121 @@
+
+// Deserializing a captured payload and serializing it again must give back
+// the same JSON, so models can be stored and replayed without losing data.
+// Payloads keep every field discord sends, including the ones that are only
+// kept in the models' `extra`.
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use discord_lib::discord::{
+    User,
+    Role,
+    GuildMember,
+    Guild,
+    Message,
+    MessageUpdate,
+    Presence,
+    UnavailableGuild,
+    DeletedMessage,
+    CreatedRole,
+    DeletedRole,
+};
+
+fn round_trip<T: Serialize + DeserializeOwned>(payload: &str) {
+    let original: Value = serde_json::from_str(payload).unwrap();
+    let parsed: T = serde_json::from_value(original.clone()).unwrap();
+    let serialized = serde_json::to_value(&parsed).unwrap();
+    
+    assert_eq!(serialized, original);
+    
+    // and once more from our own output
+    let reparsed: T = serde_json::from_value(serialized.clone()).unwrap();
+    assert_eq!(serde_json::to_value(&reparsed).unwrap(), serialized);
+}
+
+// small events don't carry anything unmodeled yet, so add a key discord might
+// send in the future and check it survives too
+fn round_trip_unknown_key<T: Serialize + DeserializeOwned>(payload: &str) {
+    round_trip::<T>(payload);
+    
+    let mut original: Value = serde_json::from_str(payload).unwrap();
+    original["new_field"] = Value::from("some value");
+    
+    round_trip::<T>(&original.to_string());
+}
+
+#[test]
+fn user() {
+    round_trip::<User>(include_str!("payloads/user.json"));
+}
+
+#[test]
+fn role() {
+    round_trip::<Role>(include_str!("payloads/role.json"));
+}
+
+#[test]
+fn guild_member() {
+    round_trip::<GuildMember>(include_str!("payloads/guild_member.json"));
+}
+
+#[test]
+fn partial_guild_member() {
+    round_trip::<GuildMember>(include_str!("payloads/guild_member_partial.json"));
+    
+    let member: GuildMember = serde_json::from_str(include_str!("payloads/guild_member_partial.json")).unwrap();
+    assert!(member.user.is_none());
+    assert_eq!(member.nick, None);
+    assert_eq!(member.premium_since, Some(None));
+}
+
+#[test]
+fn guild_create() {
+    round_trip::<Guild>(include_str!("payloads/guild_create.json"));
+    
+    let guild: Guild = serde_json::from_str(include_str!("payloads/guild_create.json")).unwrap();
+    assert_eq!(guild.extra["system_channel_flags"], 0);
+    assert_eq!(guild.extra["stage_instances"][0]["topic"], "cat talk");
+}
+
+#[test]
+fn message_create() {
+    round_trip::<Message>(include_str!("payloads/message_create.json"));
+}
+
+#[test]
+fn message_update() {
+    round_trip::<MessageUpdate>(include_str!("payloads/message_update.json"));
+}
+
+#[test]
+fn presence_update() {
+    round_trip::<Presence>(include_str!("payloads/presence_update.json"));
+    
+    let presence: Presence = serde_json::from_str(include_str!("payloads/presence_update.json")).unwrap();
+    assert_eq!(presence.user.username.as_deref(), Some("nelly"));
+    assert_eq!(presence.user.avatar, Some(None));
+    assert_eq!(presence.user.discriminator, None);
+    assert!(presence.client_status.embedded.is_some());
+}
+
+#[test]
+fn guild_delete() {
+    round_trip_unknown_key::<UnavailableGuild>(include_str!("payloads/guild_delete.json"));
+}
+
+#[test]
+fn message_delete() {
+    round_trip_unknown_key::<DeletedMessage>(include_str!("payloads/message_delete.json"));
+}
+
+#[test]
+fn guild_role_create() {
+    round_trip_unknown_key::<CreatedRole>(include_str!("payloads/guild_role_create.json"));
+}
+
+#[test]
+fn guild_role_delete() {
+    round_trip_unknown_key::<DeletedRole>(include_str!("payloads/guild_role_delete.json"));
+}